@@ -1,48 +1,75 @@
-use serde_json;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
-use crate::generator::Dungeon;
+mod ascii;
+mod compression;
+mod csv;
+mod encoding;
+mod godot;
+mod json;
+mod ldtk;
+mod pico8;
+mod registry;
+mod rust;
+mod svg;
+mod tic80;
+mod tiled;
+
+use crate::generator::painter;
+use crate::generator::paramaters::{Compression, LuaGrids, TileEncoding};
+use crate::generator::stats::FloorStats;
+use crate::generator::{mirror_rows, Dungeon, Layer, RoomType};
+pub use ascii::{render_ascii, write_dungeons_to_ascii};
+pub use csv::write_dungeons_to_csv;
+use encoding::Palette;
+pub use godot::write_dungeons_to_godot;
+pub use json::{write_dungeons_to_json, SCHEMA_VERSION as JSON_SCHEMA_VERSION};
+pub use ldtk::write_dungeons_to_ldtk;
+pub use pico8::write_dungeons_to_pico8;
+pub use registry::{Exporter, Registry};
+pub use rust::write_dungeons_to_rust;
+pub use svg::{render_svg, write_dungeons_to_svg};
+pub use tic80::write_dungeons_to_tic80;
+pub use tiled::write_dungeons_to_tiled;
 
 pub fn write_dungeons_to_file(
-    data: &Vec<Dungeon>,
-    name: &String,
+    data: &[Dungeon],
+    name: &str,
     filename: &str,
-    raycast: bool,
+    layer: Layer,
+    encoding: TileEncoding,
+    compression: Compression,
 ) -> std::io::Result<()> {
+    let palette = Palette::new(data, layer, encoding)?;
+    write_decoders(filename, &palette, compression)?;
     let mut dungeon_values: Vec<String> = Vec::new();
 
     for d in data {
         let mut dungeon = String::new();
 
-        let grid = if raycast { &d.raycast_grid } else { &d.grid };
+        let grid = if palette.is_none() && compression == Compression::None {
+            d.layer(layer).clone()
+        } else {
+            // Packed tiles don't line up with rows, write 16 bytes per line
+            floor_payload(d, layer, &palette, compression)?
+                .chunks(16)
+                .map(|chunk| chunk.iter().map(|&x| x as i32).collect())
+                .collect()
+        };
         let w = d.width.to_string();
         let h = d.height.to_string();
         let dims = format!("{}, {},\n", h, w);
         dungeon.push_str(&dims);
         let start = format!("{},{},\n", d.start_y, d.start_x);
         dungeon.push_str(&start);
-        for (i, row) in grid.iter().enumerate() {
-            let row_str = row
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>()
-                .join(",");
-
-            dungeon.push_str(&row_str);
-            if i < grid.len() - 1 {
-                dungeon.push_str(",");
-            }
-            dungeon.push_str("\n");
-        }
+        dungeon.push_str(&rows_text(&grid));
+        dungeon.push('\n');
         dungeon_values.push(dungeon);
     }
 
     let filename = format!("{}{}_maps.txt", filename, name);
-    if let Some(parent_dir) = std::path::Path::new(&filename).parent() {
-        std::fs::create_dir_all(parent_dir)?;
-    }
-    let mut file = File::create(filename)?;
+    let mut file = create_output_file(&filename)?;
 
     for (i, d) in dungeon_values.iter().enumerate() {
         let header = format!("uint8_t {}{} = {{", name, i);
@@ -60,95 +87,629 @@ pub fn write_dungeons_to_file(
     let floor_pointer_string = format!("\nuint24_t {}_floors[] = {{ {} }};\n", name, floors);
     file.write_all(floor_pointer_string.as_bytes())?;
 
+    // What each floor decompresses to
+    if compression != Compression::None {
+        let sizes = data
+            .iter()
+            .map(|d| Ok(floor_tiles(d, layer, &palette)?.len().to_string()))
+            .collect::<std::io::Result<Vec<String>>>()?
+            .join(",");
+        let sizes_string = format!("\nuint16_t {}_payload_sizes[] = {{ {} }};\n", name, sizes);
+        file.write_all(sizes_string.as_bytes())?;
+    }
+
+    if let Some(palette) = &palette {
+        let tiles = palette
+            .tiles
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        let palette_string = format!(
+            "\nuint8_t {}_tile_bits = {};\nuint8_t {}_palette[] = {{ {} }};\n",
+            name, palette.bits, name, tiles
+        );
+        file.write_all(palette_string.as_bytes())?;
+    }
+
     Ok(())
 }
 
-pub fn write_dungeons_to_lua(
-    data: &Vec<Dungeon>,
-    name: &String,
+// Directories leading up to a file about to be written
+fn create_parent_dirs(path: &str) -> std::io::Result<()> {
+    if let Some(parent_dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent_dir)?;
+    }
+    Ok(())
+}
+
+// Every exporter writes through this, so file_path doesn't have to exist yet
+fn create_output_file(path: &str) -> std::io::Result<File> {
+    create_parent_dirs(path)?;
+    File::create(path)
+}
+
+fn row_text<T: ToString>(row: &[T]) -> String {
+    row.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// Comma separated values, a line per row and a comma between rows too
+fn rows_text<T: ToString>(rows: &[Vec<T>]) -> String {
+    rows.iter()
+        .map(|row| row_text(row))
+        .collect::<Vec<String>>()
+        .join(",\n")
+}
+
+// The painter's colour of a tile id as #RRGGBB
+fn hex_color(id: i32) -> String {
+    let rgb = painter::to_color(id);
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+// Tile id through a tile name -> id table from the spec, unmapped tiles keep
+// their id
+fn map_tile(tile: i32, tile_map: &HashMap<String, u8>) -> u8 {
+    RoomType::ALL
+        .iter()
+        .find(|room_type| room_type.to_int() as i32 == tile)
+        .and_then(|room_type| tile_map.get(room_type.name()))
+        .cloned()
+        .unwrap_or(tile as u8)
+}
+
+// Places floors left to right on shelves across a fixed size map, starting a
+// new shelf under the tallest floor of the last one. None for floors that
+// don't fit anywhere.
+fn layout_floors(data: &[Dungeon], width: u32, height: u32) -> Vec<Option<(u32, u32)>> {
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    data.iter()
+        .map(|d| {
+            if d.width > width || d.height > height {
+                return None;
+            }
+            if x + d.width > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+            if y + d.height > height {
+                return None;
+            }
+            let position = (x, y);
+            x += d.width;
+            shelf_height = shelf_height.max(d.height);
+            Some(position)
+        })
+        .collect()
+}
+
+// A width x height map holding every floor that fits, placed by layout_floors
+// and mapped through tile_map, and a lua `floors` table of where each one went.
+// Floors that don't fit are left out, fantasy_console_notes names them.
+fn fantasy_console_map(
+    data: &[Dungeon],
+    (width, height): (u32, u32),
+    tile_map: &HashMap<String, u8>,
+) -> (Vec<Vec<u8>>, String) {
+    let mut map = vec![vec![0u8; width as usize]; height as usize];
+    let mut floors: Vec<String> = Vec::new();
+
+    for (i, (d, position)) in data
+        .iter()
+        .zip(layout_floors(data, width, height))
+        .enumerate()
+    {
+        let Some((x, y)) = position else {
+            continue;
+        };
+        for (row_y, row) in d.grid.iter().enumerate() {
+            for (row_x, &tile) in row.iter().enumerate() {
+                map[y as usize + row_y][x as usize + row_x] = map_tile(tile, tile_map);
+            }
+        }
+        floors.push(format!(
+            " {{floor={},x={},y={},w={},h={},start_x={},start_y={},end_x={},end_y={}}},",
+            i, x, y, d.width, d.height, d.start_x, d.start_y, d.end_x, d.end_y
+        ));
+    }
+
+    let lua = format!(
+        "-- generated by dungeonsprawler\n-- floor number, map position, size, start and end of every floor that fit\nfloors={{\n{}\n}}\n",
+        floors.join("\n")
+    );
+    (map, lua)
+}
+
+// A line for every floor fantasy_console_map leaves out
+fn fantasy_console_notes(
+    data: &[Dungeon],
+    name: &str,
+    (width, height): (u32, u32),
+    console: &str,
+) -> Vec<String> {
+    data.iter()
+        .zip(layout_floors(data, width, height))
+        .enumerate()
+        .filter(|(_, (_, position))| position.is_none())
+        .map(|(i, (d, _))| {
+            format!(
+                "{}{}: {}x{} floor does not fit in the {}x{} {} map, skipped",
+                name, i, d.width, d.height, width, height, console
+            )
+        })
+        .collect()
+}
+
+// FX flash is erased and written in 256 byte pages
+const FX_PAGE_SIZE: usize = 256;
+
+// The tiles row by row, or the packed palette indexes and the 2 bit side
+// table
+fn floor_tiles(d: &Dungeon, layer: Layer, palette: &Option<Palette>) -> std::io::Result<Vec<u8>> {
+    Ok(match palette {
+        Some(palette) => {
+            let mut tiles = palette.pack(d.layer(layer));
+            tiles.extend(palette.side_table(d.layer(layer))?);
+            tiles
+        }
+        None => d.layer(layer).iter().flatten().map(|&x| x as u8).collect(),
+    })
+}
+
+// The floor tiles compressed
+fn floor_payload(
+    d: &Dungeon,
+    layer: Layer,
+    palette: &Option<Palette>,
+    compression: Compression,
+) -> std::io::Result<Vec<u8>> {
+    Ok(compression::compress(
+        &floor_tiles(d, layer, palette)?,
+        compression,
+    ))
+}
+
+// The size of every floor before and after compression, nothing when it is off
+fn compression_notes(
+    data: &[Dungeon],
+    name: &str,
+    layer: Layer,
+    encoding: TileEncoding,
+    compression: Compression,
+) -> std::io::Result<Vec<String>> {
+    if compression == Compression::None {
+        return Ok(Vec::new());
+    }
+    let palette = Palette::new(data, layer, encoding)?;
+    let mut notes: Vec<String> = Vec::new();
+    for (i, d) in data.iter().enumerate() {
+        let tiles = floor_tiles(d, layer, &palette)?;
+        let compressed = compression::compress(&tiles, compression);
+        notes.push(format!(
+            "{}{}: {} bytes compressed to {} ({:.1}%)",
+            name,
+            i,
+            tiles.len(),
+            compressed.len(),
+            compressed.len() as f64 / tiles.len() as f64 * 100.0
+        ));
+    }
+    Ok(notes)
+}
+
+// Same layout as the fx text arrays: height, width, start y, start x, then the
+// tile payload
+fn floor_bytes(
+    d: &Dungeon,
+    layer: Layer,
+    palette: &Option<Palette>,
+    compression: Compression,
+) -> std::io::Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![
+        d.height as u8,
+        d.width as u8,
+        d.start_y as u8,
+        d.start_x as u8,
+    ];
+    bytes.extend(floor_payload(d, layer, palette, compression)?);
+    Ok(bytes)
+}
+
+// Reference C routines for reading packed or compressed tiles
+fn write_decoders(
     filename: &str,
+    palette: &Option<Palette>,
+    compression: Compression,
 ) -> std::io::Result<()> {
-    let mut dungeon_values: Vec<String> = Vec::new();
+    let mut sources: Vec<(&str, String)> = Vec::new();
+    if palette.is_some() {
+        sources.push(("tile_decoder.h", encoding::c_decoder()));
+    }
+    if compression != Compression::None {
+        sources.push(("tile_decompress.h", compression::c_decompressor()));
+    }
 
-    for d in data {
-        let dungeon_data = reverse_grid_rows(&d.grid.clone());
-        let dungeon_string = lua_dungeon_data(
-            dungeon_data.clone(),
-            d.width,
-            d.height,
-            d.start_x,
-            d.start_y,
-        );
-        dungeon_values.push(dungeon_string);
+    for (file_name, source) in sources {
+        let filename = format!("{}{}", filename, file_name);
+        let mut file = create_output_file(&filename)?;
+        file.write_all(source.as_bytes())?;
     }
-    let lua_name = format!("{}", name);
-    let data_string = format!("{} = {{\n{}\n}}", lua_name, dungeon_values.join(",\n"));
-    let lua_code = lua_text(lua_name);
 
-    let mut dungeon_raycast_values: Vec<String> = Vec::new();
+    Ok(())
+}
+
+// Writes <name>_maps.bin for the FX flash chip and <name>_maps.h with the offset
+// of every floor and of the uint24 pointer table that follows them. Packed
+// tiles get their palette after the pointer table.
+pub fn write_dungeons_to_binary(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    layer: Layer,
+    encoding: TileEncoding,
+    compression: Compression,
+) -> std::io::Result<()> {
+    let palette = Palette::new(data, layer, encoding)?;
+    write_decoders(filename, &palette, compression)?;
+    let mut bin: Vec<u8> = Vec::new();
+    let mut offsets: Vec<usize> = Vec::new();
 
     for d in data {
-        let raycast_string = lua_dungeon_data(
-            d.raycast_grid.clone(),
-            d.width,
-            d.height,
-            d.start_x,
-            d.start_y,
-        );
-        dungeon_raycast_values.push(raycast_string);
+        offsets.push(bin.len());
+        bin.extend(floor_bytes(d, layer, &palette, compression)?);
+    }
+
+    // FX data is big endian
+    let table_offset = bin.len();
+    for offset in &offsets {
+        bin.extend(&(*offset as u32).to_be_bytes()[1..]);
     }
-    let lua_name = format!("{}_raycast", name);
-    let data_raycast_string = format!(
-        "{} = {{\n{}\n}}",
-        lua_name,
-        dungeon_raycast_values.join(",\n")
-    );
-    let lua_raycast_code = lua_text(lua_name.clone());
-    let addrs = lua_addr_pairs(name);
 
-    let text = format!(
-        "{}\n{}\n{}\n{}\n{}",
-        data_string, lua_code, data_raycast_string, lua_raycast_code, addrs
+    let palette_offset = bin.len();
+    if let Some(palette) = &palette {
+        bin.extend(&palette.tiles);
+    }
+
+    // Pad to a whole page with the erased flash value
+    let data_size = bin.len();
+    let padded = data_size.div_ceil(FX_PAGE_SIZE) * FX_PAGE_SIZE;
+    bin.resize(padded, 0xFF);
+    let pages = padded / FX_PAGE_SIZE;
+
+    let mut header = String::new();
+    header.push_str("#pragma once\n\n");
+    header.push_str(
+        "// Generated by dungeonsprawler, offsets are relative to the start of the data\n\n",
     );
+    header.push_str("using uint24_t = __uint24;\n\n");
+    header.push_str(&format!(
+        "// When this is the only FX data: FX::begin({}_data_page);\n",
+        name
+    ));
+    header.push_str(&format!(
+        "constexpr uint16_t {}_data_page = 0x{:04X};\n",
+        name,
+        0x10000 - pages
+    ));
+    header.push_str(&format!(
+        "constexpr uint24_t {}_data_size = {};\n\n",
+        name, data_size
+    ));
+    for (i, offset) in offsets.iter().enumerate() {
+        header.push_str(&format!(
+            "constexpr uint24_t {}{} = 0x{:06X};\n",
+            name, i, offset
+        ));
+    }
+    if compression != Compression::None {
+        for (i, d) in data.iter().enumerate() {
+            header.push_str(&format!(
+                "constexpr uint16_t {}{}_payload_size = {};\n",
+                name,
+                i,
+                floor_tiles(d, layer, &palette)?.len()
+            ));
+        }
+    }
+    header.push_str(&format!(
+        "constexpr uint24_t {}_floors = 0x{:06X};\n",
+        name, table_offset
+    ));
+    header.push_str(&format!(
+        "constexpr uint8_t {}_floor_count = {};\n",
+        name,
+        data.len()
+    ));
+    if compression != Compression::None {
+        header.push_str("\n#include \"tile_decompress.h\"\n");
+    }
+    if let Some(palette) = &palette {
+        header.push_str(&format!(
+            "\n#include \"tile_decoder.h\"\n\nconstexpr uint24_t {}_palette = 0x{:06X};\n",
+            name, palette_offset
+        ));
+        header.push_str(&format!(
+            "constexpr uint8_t {}_palette_size = {};\n",
+            name,
+            palette.tiles.len()
+        ));
+        header.push_str(&format!(
+            "constexpr uint8_t {}_tile_bits = {};\n",
+            name, palette.bits
+        ));
+    }
 
-    let filename = format!("{}{}_maps.lua", filename, name);
-    if let Some(parent_dir) = std::path::Path::new(&filename).parent() {
-        std::fs::create_dir_all(parent_dir)?;
+    let filename = format!("{}{}_maps", filename, name);
+    let mut file = create_output_file(&format!("{}.bin", filename))?;
+    file.write_all(&bin)?;
+    let mut file = create_output_file(&format!("{}.h", filename))?;
+    file.write_all(header.as_bytes())?;
+
+    Ok(())
+}
+
+// Writes <name>.h with the floors as PROGMEM arrays that build with avr-gcc as
+// is, using the same floor layout as the binary output.
+pub fn write_dungeons_to_header(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    layer: Layer,
+    encoding: TileEncoding,
+    compression: Compression,
+) -> std::io::Result<()> {
+    let palette = Palette::new(data, layer, encoding)?;
+    write_decoders(filename, &palette, compression)?;
+
+    let guard = format!("{}_H", name.to_uppercase());
+    let mut header = String::new();
+    header.push_str(&format!("#ifndef {}\n#define {}\n\n", guard, guard));
+    header.push_str("// Generated by dungeonsprawler\n\n");
+    header.push_str("#include <stdint.h>\n\n");
+    header
+        .push_str("#ifdef __AVR__\n#include <avr/pgmspace.h>\n#else\n#define PROGMEM\n#endif\n\n");
+    if palette.is_some() {
+        header.push_str("#include \"tile_decoder.h\"\n");
+    }
+    if compression != Compression::None {
+        header.push_str("#include \"tile_decompress.h\"\n");
+    }
+    if palette.is_some() || compression != Compression::None {
+        header.push('\n');
+    }
+
+    // Shared by every dungeon header, so guarded on its own
+    header.push_str("#ifndef DUNGEON_TILE_ENUM\n#define DUNGEON_TILE_ENUM\n");
+    header.push_str("enum DungeonTile : uint8_t {\n");
+    for room_type in RoomType::ALL {
+        header.push_str(&format!(
+            "    TILE_{} = {},\n",
+            room_type.name().to_uppercase(),
+            room_type.to_int()
+        ));
+    }
+    header.push_str("};\n#endif\n\n");
+
+    if let Some(d) = data.first() {
+        header.push_str(&format!(
+            "constexpr uint8_t {}_width = {};\n",
+            name, d.width
+        ));
+        header.push_str(&format!(
+            "constexpr uint8_t {}_height = {};\n",
+            name, d.height
+        ));
+    }
+    header.push_str(&format!(
+        "constexpr uint8_t {}_floor_count = {};\n",
+        name,
+        data.len()
+    ));
+    if let Some(palette) = &palette {
+        let tiles = palette
+            .tiles
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        header.push_str(&format!(
+            "constexpr uint8_t {}_tile_bits = {};\n",
+            name, palette.bits
+        ));
+        header.push_str(&format!(
+            "const uint8_t {}_palette[] PROGMEM = {{ {} }};\n",
+            name, tiles
+        ));
+    }
+
+    for (i, d) in data.iter().enumerate() {
+        let floor_name = format!("{}{}", name, i);
+        let bytes = floor_bytes(d, layer, &palette, compression)?;
+        let rows = bytes
+            .chunks(16)
+            .map(|chunk| {
+                let row = chunk
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("    {}", row)
+            })
+            .collect::<Vec<String>>()
+            .join(",\n");
+
+        header.push_str(&format!(
+            "\nconstexpr uint8_t {}_start_x = {};\n",
+            floor_name, d.start_x
+        ));
+        header.push_str(&format!(
+            "constexpr uint8_t {}_start_y = {};\n",
+            floor_name, d.start_y
+        ));
+        if compression != Compression::None {
+            header.push_str(&format!(
+                "constexpr uint16_t {}_payload_size = {};\n",
+                floor_name,
+                floor_tiles(d, layer, &palette)?.len()
+            ));
+        }
+        header.push_str(&format!(
+            "const uint8_t {}[] PROGMEM = {{\n{}\n}};\n",
+            floor_name, rows
+        ));
+    }
+
+    let floors = (0..data.len())
+        .map(|i| format!("{}{}", name, i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    header.push_str(&format!(
+        "\nconst uint8_t * const {}_floors[] PROGMEM = {{ {} }};\n",
+        name, floors
+    ));
+    header.push_str(&format!("\n#endif // {}\n", guard));
+
+    let filename = format!("{}{}.h", filename, name);
+    let mut file = create_output_file(&filename)?;
+    file.write_all(header.as_bytes())?;
+
+    Ok(())
+}
+
+pub fn write_stats_to_file(data: &[Dungeon], name: &str, filename: &str) -> std::io::Result<()> {
+    let stats: Vec<&FloorStats> = data.iter().map(|d| &d.stats).collect();
+    let text = serde_json::to_string_pretty(&stats)?;
+
+    let filename = format!("{}{}_stats.json", filename, name);
+    let mut file = create_output_file(&filename)?;
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
+
+// Writes one FX data script for every dungeon of a spec, <name>_maps.lua for a
+// single dungeon or dungeons_maps.lua for several. Each dungeon gets a table
+// of floors and a pointer table per grid, followed by a dungeon_index with the
+// floor count and pointer table addresses of every dungeon.
+pub fn write_dungeons_to_lua(
+    dungeons: &[(&str, &[Dungeon])],
+    filename: &str,
+    grids: LuaGrids,
+) -> std::io::Result<()> {
+    let normal = grids != LuaGrids::Raycast;
+    let raycast = grids != LuaGrids::Normal;
+
+    let mut text = String::new();
+    let mut index = String::new();
+    for &(name, data) in dungeons {
+        let mut tables: Vec<String> = Vec::new();
+
+        if normal {
+            let floors = data
+                .iter()
+                .map(|d| {
+                    lua_dungeon_data(
+                        mirror_rows(d.layer(Layer::Grid)),
+                        d.width,
+                        d.height,
+                        d.start_x,
+                        d.start_y,
+                    )
+                })
+                .collect::<Vec<String>>();
+            text.push_str(&lua_table(name, &floors));
+            tables.push(format!("{}_pointers_address", name));
+        }
+
+        if raycast {
+            let lua_name = format!("{}_raycast", name);
+            let floors = data
+                .iter()
+                .map(|d| {
+                    lua_dungeon_data(
+                        d.layer(Layer::Raycast).clone(),
+                        d.width,
+                        d.height,
+                        d.start_x,
+                        d.start_y,
+                    )
+                })
+                .collect::<Vec<String>>();
+            text.push_str(&lua_table(&lua_name, &floors));
+            tables.push(format!("{}_pointers_address", lua_name));
+        }
+
+        if normal && raycast {
+            text.push_str(&lua_addr_pairs(name));
+        }
+
+        index.push_str(&format!(
+            "write(bytes({{{}}}))\nwrite(bytes({{{}}}, \"uint24\"))\n",
+            data.len(),
+            tables.join(", ")
+        ));
     }
 
-    let mut file = File::create(filename)?;
+    text.push_str(&format!(
+        "\nfield(\"dungeon_count\")\nwrite(bytes({{{}}}))\nfield(\"dungeon_index\")\n{}",
+        dungeons.len(),
+        index
+    ));
+
+    let name = match dungeons {
+        [(name, _)] => name,
+        _ => "dungeons",
+    };
+    let filename = format!("{}{}_maps.lua", filename, name);
+    let mut file = create_output_file(&filename)?;
     file.write_all(text.as_bytes())?;
 
     Ok(())
 }
 
-fn lua_text(lua_name: String) -> String {
+// The floors as a lua table followed by the code writing them and a table of
+// pointers to each floor
+fn lua_table(lua_name: &str, floors: &[String]) -> String {
+    format!(
+        "{} = {{\n{}\n}}\n{}\n",
+        lua_name,
+        floors.join(",\n"),
+        lua_text(lua_name)
+    )
+}
+
+fn lua_text(lua_name: &str) -> String {
     let lua_raycast_code = format!(
         "
 {}_pointers = {{}}
-totaloffset = 0
 field(\"{}\")
 for i,d in ipairs({}) do
     {}_pointers[i] = address()
    write(bytes(d))
 end
 field(\"{}_pointers\")
+{}_pointers_address = address()
 write(bytes({}_pointers, \"uint24\"))
 ",
-        lua_name, lua_name, lua_name, lua_name, lua_name, lua_name
+        lua_name, lua_name, lua_name, lua_name, lua_name, lua_name, lua_name
     );
     lua_raycast_code
 }
 
-fn lua_addr_pairs(name: &String) -> String {
+fn lua_addr_pairs(name: &str) -> String {
     let lua_raycast_code = format!(
         "
 field(\"{}_pairs\")
-for i,d in ipairs(test_dungeon_raycast) do
+for i,d in ipairs({}_raycast) do
     write(bytes({{{}_pointers[i],{}_raycast_pointers[i]}}, \"uint24\"))
 end
-    ",
-        name, name, name
+
+",
+        name, name, name, name
     );
     lua_raycast_code
 }
@@ -168,30 +729,7 @@ fn lua_dungeon_data(
     dungeon.push_str(&dims);
     let start = format!("{},{},\n", width - start_x - 1, start_y);
     dungeon.push_str(&start);
-    for (i, row) in grid.iter().enumerate() {
-        let row_str = row
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>()
-            .join(",");
-
-        dungeon.push_str(&row_str);
-        if i < grid.len() - 1 {
-            dungeon.push_str(",\n");
-        }
-    }
+    dungeon.push_str(&rows_text(&grid));
     dungeon.push_str("}");
     dungeon
 }
-
-fn reverse_grid_rows(map: &Vec<Vec<i32>>) -> Vec<Vec<i32>> {
-    let mut new_grid: Vec<Vec<i32>> = Vec::new();
-    for row in map.iter() {
-        let mut new_row: Vec<i32> = Vec::new();
-        for cell in row.iter().rev() {
-            new_row.push(*cell);
-        }
-        new_grid.push(new_row.clone());
-    }
-    new_grid
-}
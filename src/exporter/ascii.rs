@@ -0,0 +1,79 @@
+use std::io::prelude::*;
+
+use crate::generator::paramaters::AsciiOptions;
+use crate::generator::{Dungeon, RoomType};
+
+const RESET: &str = "\x1b[0m";
+
+// Default glyph and ANSI colour of every tile, each colour resets the last
+fn style(room_type: RoomType) -> (&'static str, &'static str) {
+    match room_type {
+        RoomType::Empty => (" ", ""),
+        RoomType::Wall => ("#", "\x1b[0;90m"),
+        RoomType::Hall => (".", ""),
+        RoomType::Start => ("S", "\x1b[0;1;32m"),
+        RoomType::End => ("E", "\x1b[0;1;31m"),
+        RoomType::Boss => ("B", "\x1b[0;35m"),
+        RoomType::Shop => ("M", "\x1b[0;33m"),
+        RoomType::Treasure => ("T", "\x1b[0;93m"),
+        RoomType::Secret => ("H", "\x1b[0;36m"),
+        RoomType::LockedDoor | RoomType::LockedDoorVertical => ("+", "\x1b[0;34m"),
+    }
+}
+
+// The floor as text, a line per row. Unknown tile ids are drawn as `?`.
+pub fn render_ascii(d: &Dungeon, options: &AsciiOptions, colour: bool) -> String {
+    let mut text = String::new();
+    for row in &d.grid {
+        // Escape codes only go out when the colour changes
+        let mut current = "";
+        for &tile in row {
+            let room_type = RoomType::ALL
+                .iter()
+                .find(|room_type| room_type.to_int() as i32 == tile);
+            let (glyph, ansi) = match room_type {
+                Some(&room_type) => {
+                    let (glyph, ansi) = style(room_type);
+                    let glyph = options
+                        .glyphs
+                        .get(room_type.name())
+                        .map_or(glyph, |glyph| glyph.as_str());
+                    (glyph, ansi)
+                }
+                None => ("?", ""),
+            };
+
+            if colour && ansi != current {
+                text.push_str(if ansi.is_empty() { RESET } else { ansi });
+                current = ansi;
+            }
+            text.push_str(glyph);
+        }
+        if !current.is_empty() {
+            text.push_str(RESET);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// Writes <name>_ascii.txt with every floor under a `<name>N:` line
+pub fn write_dungeons_to_ascii(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    options: &AsciiOptions,
+) -> std::io::Result<()> {
+    let text = data
+        .iter()
+        .enumerate()
+        .map(|(i, d)| format!("{}{}:\n{}", name, i, render_ascii(d, options, false)))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let filename = format!("{}{}_ascii.txt", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use crate::generator::paramaters::Compression;
+
+// Longest LZ match and furthest distance back a match can reach
+const LZ_MIN_MATCH: usize = 3;
+const LZ_MAX_MATCH: usize = 0x7F + LZ_MIN_MATCH;
+const LZ_MAX_LITERALS: usize = 0x80;
+const LZ_WINDOW: usize = 0x100;
+
+pub fn compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    match compression {
+        Compression::None => data.to_vec(),
+        Compression::Rle => rle(data),
+        Compression::Lz => lz(data),
+    }
+}
+
+// Pairs of (run length 1-255, value)
+fn rle(data: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && data[i + run] == data[i] && run < 255 {
+            run += 1;
+        }
+        out.push(run as u8);
+        out.push(data[i]);
+        i += run;
+    }
+    out
+}
+
+// A control byte below 0x80 is followed by that many + 1 literal bytes, from
+// 0x80 up it copies (control & 0x7F) + 3 bytes starting the next byte + 1
+// bytes back in the output. Matches may overlap the bytes they produce.
+fn lz(data: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    let mut literals: Vec<u8> = Vec::new();
+
+    let flush = |out: &mut Vec<u8>, literals: &mut Vec<u8>| {
+        for chunk in literals.chunks(LZ_MAX_LITERALS) {
+            out.push(chunk.len() as u8 - 1);
+            out.extend(chunk);
+        }
+        literals.clear();
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        for distance in 1..=LZ_WINDOW.min(i) {
+            let mut length = 0;
+            while length < LZ_MAX_MATCH
+                && i + length < data.len()
+                && data[i + length - distance] == data[i + length]
+            {
+                length += 1;
+            }
+            if length > best_length {
+                best_length = length;
+                best_distance = distance;
+            }
+        }
+
+        if best_length >= LZ_MIN_MATCH {
+            flush(&mut out, &mut literals);
+            out.push(0x80 | (best_length - LZ_MIN_MATCH) as u8);
+            out.push((best_distance - 1) as u8);
+            i += best_length;
+        } else {
+            literals.push(data[i]);
+            i += 1;
+        }
+    }
+    flush(&mut out, &mut literals);
+
+    out
+}
+
+// Reference decompressors written next to compressed maps
+pub fn c_decompressor() -> String {
+    "#pragma once
+// Generated by dungeonsprawler
+
+#include <stdint.h>
+
+// Define as pgm_read_byte(p) to decompress straight out of PROGMEM
+#ifndef DUNGEON_READ_BYTE
+#define DUNGEON_READ_BYTE(p) (*(p))
+#endif
+
+// Both decompress into `dst` until `size` bytes are written, size is the floor's
+// payload size written next to it (<name>N_payload_size, <name>_payload_sizes
+// in the fx text): width * height * bits per tile / 8, rounded up, plus the
+// rectangle table of 2 bit floors.
+
+static inline void dungeon_rle_decompress(const uint8_t *src, uint8_t *dst, uint16_t size) {
+    uint16_t written = 0;
+    while (written < size) {
+        uint8_t run = DUNGEON_READ_BYTE(src++);
+        uint8_t value = DUNGEON_READ_BYTE(src++);
+        while (run-- && written < size) {
+            dst[written++] = value;
+        }
+    }
+}
+
+static inline void dungeon_lz_decompress(const uint8_t *src, uint8_t *dst, uint16_t size) {
+    uint16_t written = 0;
+    while (written < size) {
+        uint8_t control = DUNGEON_READ_BYTE(src++);
+        if (control < 0x80) {
+            for (uint8_t i = 0; i <= control && written < size; i++) {
+                dst[written++] = DUNGEON_READ_BYTE(src++);
+            }
+        } else {
+            uint16_t from = written - DUNGEON_READ_BYTE(src++) - 1;
+            for (uint8_t i = 0; i < (control & 0x7F) + 3 && written < size; i++) {
+                dst[written++] = dst[from++];
+            }
+        }
+    }
+}
+"
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // Mirrors of dungeon_rle_decompress and dungeon_lz_decompress
+    fn rle_decompress(src: &[u8], size: usize) -> Vec<u8> {
+        let mut dst: Vec<u8> = Vec::new();
+        let mut src = src.iter();
+        while dst.len() < size {
+            let run = *src.next().unwrap();
+            let value = *src.next().unwrap();
+            for _ in 0..run {
+                if dst.len() < size {
+                    dst.push(value);
+                }
+            }
+        }
+        assert!(src.next().is_none(), "input left over");
+        dst
+    }
+
+    fn lz_decompress(src: &[u8], size: usize) -> Vec<u8> {
+        let mut dst: Vec<u8> = Vec::new();
+        let mut src = src.iter();
+        while dst.len() < size {
+            let control = *src.next().unwrap();
+            if control < 0x80 {
+                for _ in 0..=control {
+                    if dst.len() < size {
+                        dst.push(*src.next().unwrap());
+                    }
+                }
+            } else {
+                let mut from = dst.len() - *src.next().unwrap() as usize - 1;
+                for _ in 0..(control & 0x7F) as usize + 3 {
+                    if dst.len() < size {
+                        dst.push(dst[from]);
+                        from += 1;
+                    }
+                }
+            }
+        }
+        assert!(src.next().is_none(), "input left over");
+        dst
+    }
+
+    fn round_trip(data: &[u8]) {
+        assert_eq!(
+            rle_decompress(&compress(data, Compression::Rle), data.len()),
+            data
+        );
+        assert_eq!(
+            lz_decompress(&compress(data, Compression::Lz), data.len()),
+            data
+        );
+    }
+
+    #[test]
+    fn runs_longer_than_255() {
+        let mut data = vec![1; 600];
+        data.extend(vec![2; 256]);
+        data.push(3);
+        round_trip(&data);
+        assert_eq!(
+            compress(&vec![7; 256], Compression::Rle),
+            vec![255, 7, 1, 7]
+        );
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        // A two byte pattern copied from one byte back or two overlaps itself
+        let data: Vec<u8> = [1, 2].iter().cycle().take(300).cloned().collect();
+        round_trip(&data);
+        let compressed = compress(&data, Compression::Lz);
+        assert!(compressed.len() < 12, "{:?}", compressed);
+    }
+
+    #[test]
+    fn more_than_128_literals() {
+        // Nothing repeats, so 256 literals split into two runs of 128
+        let data: Vec<u8> = (0..=255).collect();
+        let compressed = compress(&data, Compression::Lz);
+        assert_eq!(compressed.len(), 258);
+        assert_eq!((compressed[0], compressed[129]), (127, 127));
+        round_trip(&data);
+
+        let mut data: Vec<u8> = (0..200).collect();
+        data.extend(vec![5; 20]);
+        round_trip(&data);
+    }
+
+    #[test]
+    fn long_match_distances() {
+        // Repeats 256 bytes back, the furthest a match reaches
+        let block: Vec<u8> = (0..=255).rev().collect();
+        let mut data = block.clone();
+        data.extend(&block);
+        round_trip(&data);
+    }
+
+    #[test]
+    fn random_tiles() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let len = rng.gen_range(0..2000);
+            let data: Vec<u8> = (0..len)
+                .map(|_| {
+                    if rng.gen_bool(0.7) {
+                        1
+                    } else {
+                        rng.gen_range(0..10)
+                    }
+                })
+                .collect();
+            round_trip(&data);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        round_trip(&[]);
+        assert!(compress(&[], Compression::Lz).is_empty());
+    }
+}
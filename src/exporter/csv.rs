@@ -0,0 +1,50 @@
+use std::io::prelude::*;
+
+use crate::generator::paramaters::CsvOptions;
+use crate::generator::Dungeon;
+
+// The raycast grid is written the same way round as the normal one
+fn floor_csv(d: &Dungeon, raycast: bool) -> String {
+    let rows = if raycast {
+        d.unmirrored_raycast_grid()
+    } else {
+        d.grid.clone()
+    };
+    rows.iter().map(|row| super::row_text(row) + "\n").collect()
+}
+
+// Writes the raw tile ids of every floor as <name>N.csv, or all of them in
+// <name>.csv with a `floor N` line before each and a blank line between them
+pub fn write_dungeons_to_csv(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    options: &CsvOptions,
+) -> std::io::Result<()> {
+    let files: Vec<(String, String)> = if options.single_file {
+        let text = data
+            .iter()
+            .enumerate()
+            .map(|(i, d)| format!("floor {}\n{}", i, floor_csv(d, options.raycast)))
+            .collect::<Vec<String>>()
+            .join("\n");
+        vec![(format!("{}{}.csv", filename, name), text)]
+    } else {
+        data.iter()
+            .enumerate()
+            .map(|(i, d)| {
+                (
+                    format!("{}{}{}.csv", filename, name, i),
+                    floor_csv(d, options.raycast),
+                )
+            })
+            .collect()
+    };
+
+    for (filename, text) in files {
+        let mut file = super::create_output_file(&filename)?;
+        file.write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Error, ErrorKind};
+
+use crate::generator::paramaters::TileEncoding;
+use crate::generator::{Dungeon, Layer};
+
+// Palette entry of 2 bit tiles that are looked up in the floor's rectangles
+pub const ESCAPE: u8 = 0xFF;
+
+pub struct Palette {
+    pub tiles: Vec<u8>,
+    pub bits: u8,
+}
+
+impl Palette {
+    // One palette of the tile ids used across every floor of a dungeon, None
+    // when tiles are stored a byte each
+    pub fn new(
+        data: &[Dungeon],
+        layer: Layer,
+        encoding: TileEncoding,
+    ) -> std::io::Result<Option<Palette>> {
+        let mut used: BTreeSet<u8> = BTreeSet::new();
+        let mut counts: HashMap<u8, usize> = HashMap::new();
+        for d in data {
+            for row in d.layer(layer) {
+                for &x in row {
+                    used.insert(x as u8);
+                    *counts.entry(x as u8).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut tiles: Vec<u8> = used.into_iter().collect();
+
+        let bits = match encoding {
+            TileEncoding::Byte => return Ok(None),
+            TileEncoding::Bits2 => {
+                // Only the 3 most common tiles fit, every other one goes
+                // through the rectangles after the packed tiles
+                if tiles.len() > 4 {
+                    tiles.sort_by(|a, b| counts[b].cmp(&counts[a]).then(a.cmp(b)));
+                    tiles.truncate(3);
+                    tiles.sort();
+                    tiles.push(ESCAPE);
+                }
+                2
+            }
+            TileEncoding::Bits4 => 4,
+            TileEncoding::Palette => (usize::BITS - (tiles.len().max(2) - 1).leading_zeros()) as u8,
+        };
+        if tiles.len() > 1 << bits {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} distinct tiles do not fit in {} bits per tile",
+                    tiles.len(),
+                    bits
+                ),
+            ));
+        }
+
+        Ok(Some(Palette { tiles, bits }))
+    }
+
+    // Palette indexes packed most significant bits first, row by row with no
+    // padding between rows, the last byte is padded with zeros
+    pub fn pack(&self, grid: &[Vec<i32>]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut bit = 0;
+        for &tile in grid.iter().flatten() {
+            let index = self
+                .tiles
+                .iter()
+                .position(|&t| t == tile as u8)
+                .unwrap_or(self.tiles.len() - 1);
+            for i in (0..self.bits).rev() {
+                if bit % 8 == 0 {
+                    bytes.push(0);
+                }
+                if index >> i & 1 == 1 {
+                    *bytes.last_mut().unwrap() |= 0x80 >> (bit % 8);
+                }
+                bit += 1;
+            }
+        }
+        bytes
+    }
+
+    // What follows the packed tiles of a 2 bit floor: a count, then x, y,
+    // width, height and tile id of rectangles covering every escaped tile.
+    // Nothing for the other encodings.
+    pub fn side_table(&self, grid: &[Vec<i32>]) -> std::io::Result<Vec<u8>> {
+        if self.bits != 2 {
+            return Ok(Vec::new());
+        }
+
+        let escaped = |tile: i32| !self.tiles.contains(&(tile as u8));
+        let mut covered = vec![vec![false; grid.first().map_or(0, |row| row.len())]; grid.len()];
+        let mut rects: Vec<[u8; 5]> = Vec::new();
+        for y in 0..grid.len() {
+            for x in 0..grid[y].len() {
+                let tile = grid[y][x];
+                if covered[y][x] || !escaped(tile) {
+                    continue;
+                }
+
+                // Widest run first, then as many rows of it as match below
+                let same = |x: usize, y: usize| grid[y][x] == tile && !covered[y][x];
+                let width = (x..grid[y].len()).take_while(|&x| same(x, y)).count();
+                let height = (y..grid.len())
+                    .take_while(|&y| (x..x + width).all(|x| same(x, y)))
+                    .count();
+                for row in covered.iter_mut().skip(y).take(height) {
+                    for cell in row.iter_mut().skip(x).take(width) {
+                        *cell = true;
+                    }
+                }
+                rects.push([x as u8, y as u8, width as u8, height as u8, tile as u8]);
+            }
+        }
+
+        if rects.len() > u8::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{} rectangles of tiles outside the 2 bit palette, use 4bit or palette",
+                    rects.len()
+                ),
+            ));
+        }
+        let mut bytes = vec![rects.len() as u8];
+        bytes.extend(rects.iter().flatten());
+        Ok(bytes)
+    }
+}
+
+// Reference decoder written next to bit packed maps
+pub fn c_decoder() -> String {
+    "#pragma once
+// Generated by dungeonsprawler
+
+#include <stdint.h>
+
+// Returns the tile id at (x, y) of a bit packed floor. `tiles` points at the
+// packed tiles after the 4 byte floor header, copied into RAM first when the
+// map lives in FX flash.
+static inline uint8_t dungeon_tile(const uint8_t *tiles, const uint8_t *palette,
+                                   uint8_t bits, uint8_t width, uint8_t x, uint8_t y) {
+    uint32_t bit = ((uint32_t)y * width + x) * bits;
+    uint8_t index = 0;
+    for (uint8_t i = 0; i < bits; i++, bit++) {
+        index = (index << 1) | ((tiles[bit >> 3] >> (7 - (bit & 7))) & 1);
+    }
+    return palette[index];
+}
+
+// 2 bit floors keep the 3 most common tiles in the palette when there are more
+// than 4, the rest are 0xFF there and found in the rectangles that follow the
+// packed tiles: a count, then x, y, width, height and tile id of each.
+static inline uint8_t dungeon_tile_2bit(const uint8_t *tiles, const uint8_t *palette,
+                                        uint8_t width, uint8_t height, uint8_t x, uint8_t y) {
+    uint8_t tile = dungeon_tile(tiles, palette, 2, width, x, y);
+    if (tile != 0xFF) {
+        return tile;
+    }
+    const uint8_t *rects = tiles + ((uint16_t)width * height * 2 + 7) / 8;
+    for (uint8_t i = 0; i < rects[0]; i++) {
+        const uint8_t *rect = rects + 1 + i * 5;
+        if (x >= rect[0] && x < rect[0] + rect[2] && y >= rect[1] && y < rect[1] + rect[3]) {
+            return rect[4];
+        }
+    }
+    return tile;
+}
+"
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same as dungeon_tile in the C decoder
+    fn unpack(tiles: &[u8], palette: &[u8], bits: u8, width: usize, x: usize, y: usize) -> u8 {
+        let base = (y * width + x) * bits as usize;
+        let mut index = 0;
+        for i in 0..bits as usize {
+            let bit = base + i;
+            index = (index << 1) | ((tiles[bit >> 3] >> (7 - (bit & 7))) & 1) as usize;
+        }
+        palette[index]
+    }
+
+    fn check(tiles: Vec<u8>, bits: u8, grid: &[Vec<i32>]) -> Vec<u8> {
+        let palette = Palette { tiles, bits };
+        let packed = palette.pack(grid);
+        let width = grid[0].len();
+        assert_eq!(
+            packed.len(),
+            (width * grid.len() * bits as usize).div_ceil(8)
+        );
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                assert_eq!(
+                    unpack(&packed, &palette.tiles, bits, width, x, y) as i32,
+                    tile,
+                    "tile {},{}",
+                    x,
+                    y
+                );
+            }
+        }
+        packed
+    }
+
+    #[test]
+    fn pack_3_bits() {
+        // 5x3 tiles are 45 bits, the last byte only holds 5 of them
+        let grid = vec![
+            vec![1, 1, 2, 3, 4],
+            vec![5, 6, 7, 9, 1],
+            vec![2, 2, 9, 7, 3],
+        ];
+        let packed = check(vec![1, 2, 3, 4, 5, 6, 7, 9], 3, &grid);
+        assert_eq!(packed.len(), 6);
+        // The end of tile 7's index (110), all of tile 3's (010), then 3 bits
+        // of zero padding
+        assert_eq!(packed[5], 0b10_010_000);
+    }
+
+    #[test]
+    fn pack_4_bits() {
+        // 3x3 tiles are 36 bits, the last byte holds a single tile
+        let grid = vec![vec![0, 1, 2], vec![9, 10, 5], vec![8, 7, 10]];
+        let packed = check((0..=10).collect(), 4, &grid);
+        assert_eq!(packed, vec![0x01, 0x29, 0xA5, 0x87, 0xA0]);
+    }
+
+    #[test]
+    fn side_table_covers_escaped_tiles() {
+        let grid = vec![
+            vec![1, 1, 1, 1, 1],
+            vec![1, 5, 5, 9, 1],
+            vec![1, 5, 5, 2, 3],
+            vec![1, 1, 1, 1, 1],
+        ];
+        let palette = Palette {
+            tiles: vec![1, 2, 3, ESCAPE],
+            bits: 2,
+        };
+        let table = palette.side_table(&grid).unwrap();
+        assert_eq!(table, vec![2, 1, 1, 2, 2, 5, 3, 1, 1, 1, 9]);
+    }
+}
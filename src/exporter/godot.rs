@@ -0,0 +1,130 @@
+use std::io::prelude::*;
+
+use crate::generator::painter;
+use crate::generator::paramaters::GodotOptions;
+use crate::generator::{Dungeon, RoomType};
+
+const TILE_SIZE: u32 = 8;
+
+fn atlas_coords(room_type: RoomType, options: &GodotOptions) -> [u16; 2] {
+    options
+        .atlas
+        .get(room_type.name())
+        .cloned()
+        .unwrap_or([room_type.to_int() as u16, 0])
+}
+
+// TileMapLayer cell data: a format version, then per cell x, y, source id,
+// atlas x, atlas y and alternative tile, all little endian 16 bit
+fn tile_map_data(d: &Dungeon, options: &GodotOptions) -> String {
+    let mut bytes: Vec<u8> = vec![0, 0];
+    for (y, row) in d.grid.iter().enumerate() {
+        for (x, &tile) in row.iter().enumerate() {
+            let [atlas_x, atlas_y] = RoomType::ALL
+                .iter()
+                .find(|room_type| room_type.to_int() as i32 == tile)
+                .map_or([tile as u16, 0], |&room_type| {
+                    atlas_coords(room_type, options)
+                });
+            for value in [x as u16, y as u16, 0, atlas_x, atlas_y, 0] {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+    }
+    bytes
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Writes <name>.tscn with a Floor node per floor holding a TileMapLayer and
+// Start and End markers, only the first floor is visible. Without a texture in
+// the options the generated <name>_godot_tileset.png is used as the atlas.
+pub fn write_dungeons_to_godot(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    options: &GodotOptions,
+) -> std::io::Result<()> {
+    let tile_size = options.tile_size.unwrap_or(TILE_SIZE);
+    let texture = match &options.texture {
+        Some(texture) => texture.clone(),
+        None => {
+            let tileset = format!("{}_godot_tileset.png", name);
+            let tileset_path = format!("{}{}", filename, tileset);
+            super::create_parent_dirs(&tileset_path)?;
+            painter::paint_tileset(RoomType::ALL.len() as u32, tile_size, &tileset_path)?;
+            tileset
+        }
+    };
+
+    let mut atlas: Vec<[u16; 2]> = RoomType::ALL
+        .iter()
+        .map(|&room_type| atlas_coords(room_type, options))
+        .collect();
+    atlas.sort();
+    atlas.dedup();
+
+    let mut text = String::new();
+    text.push_str("[gd_scene load_steps=4 format=3]\n\n");
+    text.push_str(&format!(
+        "[ext_resource type=\"Texture2D\" path=\"{}\" id=\"1_tiles\"]\n\n",
+        texture
+    ));
+    text.push_str("[sub_resource type=\"TileSetAtlasSource\" id=\"TileSetAtlasSource_1\"]\n");
+    text.push_str("texture = ExtResource(\"1_tiles\")\n");
+    text.push_str(&format!(
+        "texture_region_size = Vector2i({}, {})\n",
+        tile_size, tile_size
+    ));
+    for [x, y] in atlas {
+        text.push_str(&format!("{}:{}/0 = 0\n", x, y));
+    }
+    text.push_str("\n[sub_resource type=\"TileSet\" id=\"TileSet_1\"]\n");
+    text.push_str(&format!(
+        "tile_size = Vector2i({}, {})\n",
+        tile_size, tile_size
+    ));
+    text.push_str("sources/0 = SubResource(\"TileSetAtlasSource_1\")\n\n");
+
+    text.push_str(&format!("[node name=\"{}\" type=\"Node2D\"]\n", name));
+    let center = |x: u32, y: u32| {
+        format!(
+            "Vector2({}, {})",
+            x * tile_size + tile_size / 2,
+            y * tile_size + tile_size / 2
+        )
+    };
+    for (i, d) in data.iter().enumerate() {
+        let floor = format!("Floor{}", i);
+        text.push_str(&format!(
+            "\n[node name=\"{}\" type=\"Node2D\" parent=\".\"]\n",
+            floor
+        ));
+        if i > 0 {
+            text.push_str("visible = false\n");
+        }
+        text.push_str(&format!(
+            "\n[node name=\"Tiles\" type=\"TileMapLayer\" parent=\"{}\"]\ntile_map_data = PackedByteArray({})\ntile_set = SubResource(\"TileSet_1\")\n",
+            floor,
+            tile_map_data(d, options)
+        ));
+        text.push_str(&format!(
+            "\n[node name=\"Start\" type=\"Marker2D\" parent=\"{}\"]\nposition = {}\n",
+            floor,
+            center(d.start_x, d.start_y)
+        ));
+        text.push_str(&format!(
+            "\n[node name=\"End\" type=\"Marker2D\" parent=\"{}\"]\nposition = {}\n",
+            floor,
+            center(d.end_x, d.end_y)
+        ));
+    }
+
+    let filename = format!("{}{}.tscn", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
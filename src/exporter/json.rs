@@ -0,0 +1,123 @@
+use serde::Serialize;
+use std::io::prelude::*;
+
+use crate::generator::stats::FloorStats;
+use crate::generator::{Dungeon, RoomType};
+
+// Bump when a field is renamed, removed or changes meaning, adding fields keeps
+// the version
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct DungeonJson<'a> {
+    schema_version: u32,
+    name: &'a str,
+    floors: Vec<FloorJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+// Bounds of the room floor, without the wall ring
+#[derive(Serialize)]
+struct RoomJson {
+    #[serde(rename = "type")]
+    room_type: &'static str,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize)]
+struct DoorJson {
+    x: u32,
+    y: u32,
+    orientation: &'static str,
+}
+
+#[derive(Serialize)]
+struct FloorJson<'a> {
+    // Left out for imported floors, they have no seed to regenerate them from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    width: u32,
+    height: u32,
+    start: Point,
+    end: Point,
+    grid: &'a Vec<Vec<i32>>,
+    raycast_grid: Vec<Vec<i32>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    autotile_grid: &'a Vec<Vec<i32>>,
+    rooms: Vec<RoomJson>,
+    doors: Vec<DoorJson>,
+    stats: &'a FloorStats,
+}
+
+fn floor_json(d: &Dungeon) -> FloorJson<'_> {
+    let rooms = d
+        .placed_rooms()
+        .iter()
+        .filter(|room| room.room_type != RoomType::Start && room.room_type != RoomType::End)
+        .map(|room| RoomJson {
+            room_type: room.room_type.name(),
+            x: room.x + 1,
+            y: room.y + 1,
+            width: room.width - 2,
+            height: room.height - 2,
+        })
+        .collect();
+
+    let doors = d
+        .doors()
+        .into_iter()
+        .map(|(x, y, orientation)| DoorJson {
+            x,
+            y,
+            orientation: if orientation == RoomType::LockedDoor {
+                "horizontal"
+            } else {
+                "vertical"
+            },
+        })
+        .collect();
+
+    FloorJson {
+        seed: d.seed,
+        width: d.width,
+        height: d.height,
+        start: Point {
+            x: d.start_x,
+            y: d.start_y,
+        },
+        end: Point {
+            x: d.end_x,
+            y: d.end_y,
+        },
+        grid: &d.grid,
+        raycast_grid: d.unmirrored_raycast_grid(),
+        autotile_grid: &d.autotile_grid,
+        rooms,
+        doors,
+        stats: &d.stats,
+    }
+}
+
+// Writes <name>.json with every floor and everything known about it, the
+// schema is documented in the README
+pub fn write_dungeons_to_json(data: &[Dungeon], name: &str, filename: &str) -> std::io::Result<()> {
+    let dungeon = DungeonJson {
+        schema_version: SCHEMA_VERSION,
+        name,
+        floors: data.iter().map(floor_json).collect(),
+    };
+
+    let filename = format!("{}{}.json", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(serde_json::to_string_pretty(&dungeon)?.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,434 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::io::prelude::*;
+
+use crate::generator::{Dungeon, RoomType};
+
+const GRID_SIZE: u32 = 8;
+const LDTK_VERSION: &str = "1.5.3";
+
+const TILES_LAYER_UID: u32 = 1;
+const ENTITIES_LAYER_UID: u32 = 2;
+// Entity definitions as (identifier, uid, field name, field uid, colour)
+const ENTITIES: [(&str, u32, Option<&str>, u32, &str); 4] = [
+    ("Start", 10, None, 0, "#00FF00"),
+    ("End", 11, None, 0, "#FF0000"),
+    ("Door", 12, Some("orientation"), 20, "#FF00FF"),
+    ("Room", 13, Some("type"), 21, "#FFA500"),
+];
+const FIRST_LEVEL_UID: u32 = 100;
+
+// Iids follow from the floor seeds so the same spec and seed write the same
+// file, salted with the level number to keep them apart when seeds repeat
+struct Iids(StdRng);
+
+impl Iids {
+    fn new(seed: u64, salt: u64) -> Iids {
+        Iids(StdRng::seed_from_u64(
+            seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+        ))
+    }
+
+    fn next(&mut self) -> String {
+        let rng = &mut self.0;
+        format!(
+            "{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+            rng.gen::<u32>(),
+            rng.gen::<u16>(),
+            rng.gen::<u16>() & 0xFFF,
+            rng.gen::<u16>() & 0xFFF,
+            rng.gen::<u64>() & 0xFFFF_FFFF_FFFF
+        )
+    }
+}
+
+fn layer_def(identifier: &str, kind: &str, uid: u32) -> Value {
+    let int_grid_values: Vec<Value> = if kind == "IntGrid" {
+        // 0 is an empty cell in LDtk, so Empty needs no value of its own
+        RoomType::ALL
+            .iter()
+            .filter(|room_type| **room_type != RoomType::Empty)
+            .map(|room_type| {
+                json!({
+                    "value": room_type.to_int(),
+                    "identifier": room_type.name(),
+                    "color": super::hex_color(room_type.to_int() as i32),
+                    "tile": null,
+                    "groupUid": 0,
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    json!({
+        "__type": kind,
+        "identifier": identifier,
+        "type": kind,
+        "uid": uid,
+        "doc": null,
+        "uiColor": null,
+        "gridSize": GRID_SIZE,
+        "guideGridWid": 0,
+        "guideGridHei": 0,
+        "displayOpacity": 1,
+        "inactiveOpacity": 1,
+        "hideInList": false,
+        "hideFieldsWhenInactive": false,
+        "canSelectWhenInactive": true,
+        "renderInWorldView": true,
+        "pxOffsetX": 0,
+        "pxOffsetY": 0,
+        "parallaxFactorX": 0,
+        "parallaxFactorY": 0,
+        "parallaxScaling": true,
+        "requiredTags": [],
+        "excludedTags": [],
+        "autoTilesKilledByOtherLayerUid": null,
+        "uiFilterTags": [],
+        "useAsyncRender": false,
+        "intGridValues": int_grid_values,
+        "intGridValuesGroups": [],
+        "autoRuleGroups": [],
+        "autoSourceLayerDefUid": null,
+        "tilesetDefUid": null,
+        "tilePivotX": 0,
+        "tilePivotY": 0,
+        "biomeFieldUid": null,
+    })
+}
+
+fn field_def(identifier: &str, uid: u32) -> Value {
+    json!({
+        "identifier": identifier,
+        "doc": null,
+        "__type": "String",
+        "uid": uid,
+        "type": "F_String",
+        "isArray": false,
+        "canBeNull": true,
+        "arrayMinLength": null,
+        "arrayMaxLength": null,
+        "editorDisplayMode": "ValueOnly",
+        "editorDisplayScale": 1,
+        "editorDisplayPos": "Above",
+        "editorLinkStyle": "StraightArrow",
+        "editorDisplayColor": null,
+        "editorAlwaysShow": false,
+        "editorShowInWorld": true,
+        "editorCutLongValues": true,
+        "editorTextSuffix": null,
+        "editorTextPrefix": null,
+        "useForSmartColor": false,
+        "exportToToc": false,
+        "searchable": false,
+        "min": null,
+        "max": null,
+        "regex": null,
+        "acceptFileTypes": null,
+        "defaultOverride": null,
+        "textLanguageMode": null,
+        "symmetricalRef": false,
+        "autoChainRef": true,
+        "allowOutOfLevelRef": true,
+        "allowedRefs": "OnlySame",
+        "allowedRefsEntityUid": null,
+        "allowedRefTags": [],
+        "tilesetUid": null,
+    })
+}
+
+fn entity_defs() -> Vec<Value> {
+    ENTITIES
+        .iter()
+        .map(|(identifier, uid, field, field_uid, color)| {
+            let fields: Vec<Value> = field
+                .iter()
+                .map(|field| field_def(field, *field_uid))
+                .collect();
+            let resizable = *identifier == "Room";
+            json!({
+                "identifier": identifier,
+                "uid": uid,
+                "tags": [],
+                "exportToToc": false,
+                "allowOutOfBounds": false,
+                "doc": null,
+                "width": GRID_SIZE,
+                "height": GRID_SIZE,
+                "resizableX": resizable,
+                "resizableY": resizable,
+                "minWidth": null,
+                "maxWidth": null,
+                "minHeight": null,
+                "maxHeight": null,
+                "keepAspectRatio": false,
+                "tileOpacity": 1,
+                "fillOpacity": 0.08,
+                "lineOpacity": 1,
+                "hollow": resizable,
+                "color": color,
+                "renderMode": "Rectangle",
+                "showName": true,
+                "tilesetId": null,
+                "tileRenderMode": "FitInside",
+                "tileRect": null,
+                "uiTileRect": null,
+                "nineSliceBorders": [],
+                "maxCount": 0,
+                "limitScope": "PerLevel",
+                "limitBehavior": "MoveLastOne",
+                "pivotX": 0,
+                "pivotY": 0,
+                "fieldDefs": fields,
+            })
+        })
+        .collect()
+}
+
+// Cell position and size of an entity plus the value of its field, if any
+fn entity_instance(
+    iids: &mut Iids,
+    identifier: &str,
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+    value: Option<&str>,
+    world: (u32, u32),
+) -> Value {
+    let (_, uid, field, field_uid, color) = ENTITIES
+        .iter()
+        .find(|entity| entity.0 == identifier)
+        .unwrap();
+    let fields: Vec<Value> = field
+        .iter()
+        .map(|field| {
+            json!({
+                "__identifier": field,
+                "__type": "String",
+                "__value": value,
+                "__tile": null,
+                "defUid": field_uid,
+                "realEditorValues": [{ "id": "V_String", "params": [value] }],
+            })
+        })
+        .collect();
+
+    json!({
+        "__identifier": identifier,
+        "__grid": [x, y],
+        "__pivot": [0, 0],
+        "__tags": [],
+        "__tile": null,
+        "__smartColor": color,
+        "__worldX": world.0 + x * GRID_SIZE,
+        "__worldY": world.1 + y * GRID_SIZE,
+        "iid": iids.next(),
+        "width": width * GRID_SIZE,
+        "height": height * GRID_SIZE,
+        "defUid": uid,
+        "px": [x * GRID_SIZE, y * GRID_SIZE],
+        "fieldInstances": fields,
+    })
+}
+
+fn layer_instance(
+    iids: &mut Iids,
+    identifier: &str,
+    kind: &str,
+    uid: u32,
+    d: &Dungeon,
+    level_uid: u32,
+) -> Value {
+    json!({
+        "__identifier": identifier,
+        "__type": kind,
+        "__cWid": d.width,
+        "__cHei": d.height,
+        "__gridSize": GRID_SIZE,
+        "__opacity": 1,
+        "__pxTotalOffsetX": 0,
+        "__pxTotalOffsetY": 0,
+        "__tilesetDefUid": null,
+        "__tilesetRelPath": null,
+        "iid": iids.next(),
+        "levelId": level_uid,
+        "layerDefUid": uid,
+        "pxOffsetX": 0,
+        "pxOffsetY": 0,
+        "visible": true,
+        "optionalRules": [],
+        "intGridCsv": [],
+        "autoLayerTiles": [],
+        "seed": 0,
+        "overrideTilesetUid": null,
+        "gridTiles": [],
+        "entityInstances": [],
+    })
+}
+
+fn level(d: &Dungeon, index: u32, world: (u32, u32)) -> Value {
+    let uid = FIRST_LEVEL_UID + index;
+    let mut iids = Iids::new(d.seed.unwrap_or(0), index as u64 + 1);
+
+    let mut entities: Vec<Value> = vec![
+        entity_instance(
+            &mut iids,
+            "Start",
+            (d.start_x, d.start_y),
+            (1, 1),
+            None,
+            world,
+        ),
+        entity_instance(&mut iids, "End", (d.end_x, d.end_y), (1, 1), None, world),
+    ];
+    for (x, y, orientation) in d.doors() {
+        let orientation = if orientation == RoomType::LockedDoor {
+            "horizontal"
+        } else {
+            "vertical"
+        };
+        entities.push(entity_instance(
+            &mut iids,
+            "Door",
+            (x, y),
+            (1, 1),
+            Some(orientation),
+            world,
+        ));
+    }
+    for room in d.placed_rooms() {
+        if room.room_type == RoomType::Start || room.room_type == RoomType::End {
+            continue;
+        }
+        entities.push(entity_instance(
+            &mut iids,
+            "Room",
+            (room.x + 1, room.y + 1),
+            (room.width - 2, room.height - 2),
+            Some(room.room_type.name()),
+            world,
+        ));
+    }
+
+    let mut entity_layer = layer_instance(
+        &mut iids,
+        "Entities",
+        "Entities",
+        ENTITIES_LAYER_UID,
+        d,
+        uid,
+    );
+    entity_layer["entityInstances"] = json!(entities);
+    let mut tile_layer = layer_instance(&mut iids, "Tiles", "IntGrid", TILES_LAYER_UID, d, uid);
+    tile_layer["intGridCsv"] = json!(d.grid.iter().flatten().collect::<Vec<&i32>>());
+
+    json!({
+        "identifier": format!("Floor_{}", index),
+        "iid": iids.next(),
+        "uid": uid,
+        "worldX": world.0,
+        "worldY": world.1,
+        "worldDepth": 0,
+        "pxWid": d.width * GRID_SIZE,
+        "pxHei": d.height * GRID_SIZE,
+        "__bgColor": "#696A79",
+        "bgColor": null,
+        "useAutoIdentifier": false,
+        "bgRelPath": null,
+        "bgPos": null,
+        "bgPivotX": 0.5,
+        "bgPivotY": 0.5,
+        "__smartColor": "#ADADB5",
+        "__bgPos": null,
+        "externalRelPath": null,
+        "fieldInstances": [],
+        "layerInstances": [entity_layer, tile_layer],
+        "__neighbours": [],
+    })
+}
+
+// Writes <name>.ldtk with one level per floor laid out left to right, an
+// IntGrid layer of tile ids and entities for start, end, doors and rooms.
+pub fn write_dungeons_to_ldtk(data: &[Dungeon], name: &str, filename: &str) -> std::io::Result<()> {
+    let mut levels: Vec<Value> = Vec::new();
+    let mut world_x = 0;
+    for (i, d) in data.iter().enumerate() {
+        levels.push(level(d, i as u32, (world_x, 0)));
+        world_x += (d.width + 4) * GRID_SIZE;
+    }
+
+    let mut iids = Iids::new(
+        data.iter()
+            .fold(0, |seed, d| seed.rotate_left(5) ^ d.seed.unwrap_or(0)),
+        0,
+    );
+    let (width, height) = data
+        .first()
+        .map(|d| (d.width * GRID_SIZE, d.height * GRID_SIZE))
+        .unwrap_or((256, 256));
+    let project = json!({
+        "__header__": {
+            "fileType": "LDtk Project JSON",
+            "app": "LDtk",
+            "doc": "https://ldtk.io/json",
+            "schema": "https://ldtk.io/files/JSON_SCHEMA.json",
+            "appAuthor": "Sebastien 'deepnight' Benard",
+            "appVersion": LDTK_VERSION,
+            "url": "https://ldtk.io",
+        },
+        "iid": iids.next(),
+        "jsonVersion": LDTK_VERSION,
+        "appBuildId": 0,
+        "nextUid": FIRST_LEVEL_UID + data.len() as u32,
+        "identifierStyle": "Capitalize",
+        "toc": [],
+        "worldLayout": "Free",
+        "worldGridWidth": width,
+        "worldGridHeight": height,
+        "defaultLevelWidth": width,
+        "defaultLevelHeight": height,
+        "defaultPivotX": 0,
+        "defaultPivotY": 0,
+        "defaultGridSize": GRID_SIZE,
+        "defaultEntityWidth": GRID_SIZE,
+        "defaultEntityHeight": GRID_SIZE,
+        "bgColor": "#40465B",
+        "defaultLevelBgColor": "#696A79",
+        "minifyJson": false,
+        "externalLevels": false,
+        "exportTiled": false,
+        "simplifiedExport": false,
+        "imageExportMode": "None",
+        "exportLevelBg": true,
+        "pngFilePattern": null,
+        "backupOnSave": false,
+        "backupLimit": 10,
+        "backupRelPath": null,
+        "levelNamePattern": "Floor_%idx",
+        "tutorialDesc": null,
+        "customCommands": [],
+        "flags": [],
+        "defs": {
+            "layers": [
+                layer_def("Entities", "Entities", ENTITIES_LAYER_UID),
+                layer_def("Tiles", "IntGrid", TILES_LAYER_UID),
+            ],
+            "entities": entity_defs(),
+            "tilesets": [],
+            "enums": [],
+            "externalEnums": [],
+            "levelFields": [],
+        },
+        "levels": levels,
+        "worlds": [],
+        "dummyWorldIid": iids.next(),
+    });
+
+    let filename = format!("{}{}.ldtk", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(serde_json::to_string_pretty(&project)?.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::io::prelude::*;
+
+use crate::generator::paramaters::Pico8Options;
+use crate::generator::Dungeon;
+
+const MAP_WIDTH: u32 = 128;
+// The bottom half of the map is the same memory as sprites 128-255
+const MAP_HEIGHT: u32 = 32;
+const SHARED_MAP_HEIGHT: u32 = 64;
+
+fn hex(values: &[u8]) -> String {
+    values.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+// Map rows 32-63 written as the bottom half of the sprite sheet, a pixel per
+// nibble with the low nibble first
+fn shared_gfx(map: &[Vec<u8>]) -> String {
+    let mut text = String::from("__gfx__\n");
+    let blank = "0".repeat(MAP_WIDTH as usize);
+    for _ in 0..64 {
+        text.push_str(&blank);
+        text.push('\n');
+    }
+    for row in &map[MAP_HEIGHT as usize..] {
+        for half in row.chunks(MAP_WIDTH as usize / 2) {
+            for byte in half {
+                text.push_str(&format!("{:x}{:x}", byte & 0xF, byte >> 4));
+            }
+            text.push('\n');
+        }
+    }
+    text
+}
+
+// The shared map adds the 32 rows kept in the bottom half of __gfx__
+fn map_size(options: &Pico8Options) -> (u32, u32) {
+    if options.shared_map {
+        (MAP_WIDTH, SHARED_MAP_HEIGHT)
+    } else {
+        (MAP_WIDTH, MAP_HEIGHT)
+    }
+}
+
+// A line for every floor the cart leaves out
+pub fn skipped_floors(data: &[Dungeon], name: &str, options: &Pico8Options) -> Vec<String> {
+    super::fantasy_console_notes(data, name, map_size(options), "pico-8")
+}
+
+// Writes <name>.p8 with every floor that fits laid out in the __map__ and a
+// `floors` lua table of where each one went. Floors that don't fit are left
+// out, skipped_floors reports them.
+pub fn write_dungeons_to_pico8(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    options: &Pico8Options,
+) -> std::io::Result<()> {
+    let (map, floors) = super::fantasy_console_map(data, map_size(options), &options.tile_map);
+
+    let mut text = String::new();
+    text.push_str("pico-8 cartridge // http://www.pico-8.com\nversion 41\n");
+    text.push_str("__lua__\n");
+    text.push_str(&floors);
+    if options.shared_map {
+        text.push_str(&shared_gfx(&map));
+    }
+    text.push_str("__map__\n");
+    for row in &map[..MAP_HEIGHT as usize] {
+        text.push_str(&hex(row));
+        text.push('\n');
+    }
+
+    let filename = format!("{}{}.p8", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,574 @@
+use std::collections::HashMap;
+
+use crate::generator::paramaters::{DungeonParameters, TileEncoding};
+use crate::generator::{Dungeon, Layer};
+
+// An output format. It is handed every dungeon of a spec at once, formats that
+// write each dungeon on its own only implement write_dungeon.
+pub trait Exporter {
+    // Name used in output_format and output_formats
+    fn name(&self) -> &str;
+
+    // Extension of the main file it writes, without the dot
+    fn extension(&self) -> &str;
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()>;
+
+    // Lines for the user about what write_dungeon leaves out or how it packs
+    // the floors. The caller prints them, a line several formats share once.
+    fn notes(
+        &self,
+        _floors: &[Dungeon],
+        _name: &str,
+        _params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn write(
+        &self,
+        dungeons: &[(String, Vec<Dungeon>)],
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        for (name, floors) in dungeons {
+            self.write_dungeon(floors, name, params)?;
+        }
+        Ok(())
+    }
+}
+
+// Output formats by name, new() comes with every built in format
+pub struct Registry {
+    exporters: Vec<Box<dyn Exporter>>,
+    aliases: HashMap<String, String>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        let mut registry = Registry::empty();
+        registry.register(Box::new(Fx));
+        registry.register(Box::new(Lua));
+        registry.alias("raycast-lua", "raycast");
+        registry.register(Box::new(Binary));
+        registry.register(Box::new(Header));
+        registry.register(Box::new(Tiled { json: false }));
+        registry.register(Box::new(Tiled { json: true }));
+        registry.register(Box::new(Json));
+        registry.register(Box::new(Ldtk));
+        registry.register(Box::new(Pico8));
+        registry.register(Box::new(Tic80 { cart: true }));
+        registry.register(Box::new(Tic80 { cart: false }));
+        registry.register(Box::new(Godot));
+        registry.register(Box::new(Rust));
+        registry.register(Box::new(Ascii));
+        registry.register(Box::new(Csv));
+        registry.register(Box::new(Svg));
+        registry
+    }
+
+    pub fn empty() -> Registry {
+        Registry {
+            exporters: Vec::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    // Replaces any exporter already registered under the same name
+    pub fn register(&mut self, exporter: Box<dyn Exporter>) {
+        self.exporters.retain(|e| e.name() != exporter.name());
+        self.exporters.push(exporter);
+    }
+
+    pub fn alias(&mut self, alias: &str, name: &str) {
+        self.aliases.insert(alias.to_string(), name.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        let name = self.aliases.get(name).map_or(name, |name| name.as_str());
+        self.exporters
+            .iter()
+            .find(|e| e.name() == name)
+            .map(|e| e.as_ref())
+    }
+
+    // Looks up every format, each exporter once even when named through an
+    // alias as well
+    pub fn resolve(&self, names: &[String]) -> Result<Vec<&dyn Exporter>, String> {
+        let mut exporters: Vec<&dyn Exporter> = Vec::new();
+        for name in names {
+            let exporter = self.get(name).ok_or_else(|| {
+                format!(
+                    "unknown output format {}, supported formats are: {}",
+                    name,
+                    self.supported()
+                )
+            })?;
+            if !exporters.iter().any(|e| e.name() == exporter.name()) {
+                exporters.push(exporter);
+            }
+        }
+        Ok(exporters)
+    }
+
+    fn supported(&self) -> String {
+        let mut names: Vec<String> = self
+            .exporters
+            .iter()
+            .map(|e| format!("{} (.{})", e.name(), e.extension()))
+            .collect();
+        let mut aliases: Vec<String> = self
+            .aliases
+            .iter()
+            .map(|(alias, name)| format!("{} (same as {})", alias, name))
+            .collect();
+        aliases.sort();
+        names.extend(aliases);
+        names.join(", ")
+    }
+}
+
+struct Fx;
+
+impl Exporter for Fx {
+    fn name(&self) -> &str {
+        "fx"
+    }
+
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_file(
+            floors,
+            name,
+            &params.file_path,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )?;
+
+        // Wall masks take far more distinct values than a packed palette
+        // holds, so the autotile layer always stays a byte per tile
+        if floors.iter().any(|floor| !floor.autotile_grid.is_empty()) {
+            super::write_dungeons_to_file(
+                floors,
+                &format!("{}_autotile", name),
+                &params.file_path,
+                Layer::Autotile,
+                TileEncoding::Byte,
+                params.compression,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn notes(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        let mut notes = super::compression_notes(
+            floors,
+            name,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )?;
+        if floors.iter().any(|floor| !floor.autotile_grid.is_empty()) {
+            notes.extend(super::compression_notes(
+                floors,
+                &format!("{}_autotile", name),
+                Layer::Autotile,
+                TileEncoding::Byte,
+                params.compression,
+            )?);
+        }
+        Ok(notes)
+    }
+}
+
+// All dungeons go into one script
+struct Lua;
+
+impl Exporter for Lua {
+    fn name(&self) -> &str {
+        "raycast"
+    }
+
+    fn extension(&self) -> &str {
+        "lua"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_lua(&[(name, floors)], &params.file_path, params.lua_grids)
+    }
+
+    fn write(
+        &self,
+        dungeons: &[(String, Vec<Dungeon>)],
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        let dungeons: Vec<(&str, &[Dungeon])> = dungeons
+            .iter()
+            .map(|(name, floors)| (name.as_str(), floors.as_slice()))
+            .collect();
+        super::write_dungeons_to_lua(&dungeons, &params.file_path, params.lua_grids)
+    }
+}
+
+struct Binary;
+
+impl Exporter for Binary {
+    fn name(&self) -> &str {
+        "binary"
+    }
+
+    fn extension(&self) -> &str {
+        "bin"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_binary(
+            floors,
+            name,
+            &params.file_path,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )
+    }
+
+    fn notes(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        super::compression_notes(
+            floors,
+            name,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )
+    }
+}
+
+struct Header;
+
+impl Exporter for Header {
+    fn name(&self) -> &str {
+        "header"
+    }
+
+    fn extension(&self) -> &str {
+        "h"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_header(
+            floors,
+            name,
+            &params.file_path,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )
+    }
+
+    fn notes(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        super::compression_notes(
+            floors,
+            name,
+            Layer::Grid,
+            params.tile_encoding,
+            params.compression,
+        )
+    }
+}
+
+struct Tiled {
+    json: bool,
+}
+
+impl Exporter for Tiled {
+    fn name(&self) -> &str {
+        if self.json {
+            "tiled-json"
+        } else {
+            "tiled"
+        }
+    }
+
+    fn extension(&self) -> &str {
+        if self.json {
+            "tmj"
+        } else {
+            "tmx"
+        }
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_tiled(floors, name, &params.file_path, self.json)
+    }
+}
+
+struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_json(floors, name, &params.file_path)
+    }
+}
+
+struct Ldtk;
+
+impl Exporter for Ldtk {
+    fn name(&self) -> &str {
+        "ldtk"
+    }
+
+    fn extension(&self) -> &str {
+        "ldtk"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_ldtk(floors, name, &params.file_path)
+    }
+}
+
+struct Pico8;
+
+impl Exporter for Pico8 {
+    fn name(&self) -> &str {
+        "pico8"
+    }
+
+    fn extension(&self) -> &str {
+        "p8"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_pico8(floors, name, &params.file_path, &params.pico8)
+    }
+
+    fn notes(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        Ok(super::pico8::skipped_floors(floors, name, &params.pico8))
+    }
+}
+
+struct Tic80 {
+    cart: bool,
+}
+
+impl Exporter for Tic80 {
+    fn name(&self) -> &str {
+        if self.cart {
+            "tic80"
+        } else {
+            "tic80-map"
+        }
+    }
+
+    fn extension(&self) -> &str {
+        if self.cart {
+            "tic"
+        } else {
+            "map"
+        }
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_tic80(floors, name, &params.file_path, &params.tic80, self.cart)
+    }
+
+    fn notes(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        _params: &DungeonParameters,
+    ) -> std::io::Result<Vec<String>> {
+        Ok(super::tic80::skipped_floors(floors, name))
+    }
+}
+
+struct Godot;
+
+impl Exporter for Godot {
+    fn name(&self) -> &str {
+        "godot"
+    }
+
+    fn extension(&self) -> &str {
+        "tscn"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_godot(floors, name, &params.file_path, &params.godot)
+    }
+}
+
+struct Rust;
+
+impl Exporter for Rust {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn extension(&self) -> &str {
+        "rs"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_rust(floors, name, &params.file_path)
+    }
+}
+
+struct Ascii;
+
+impl Exporter for Ascii {
+    fn name(&self) -> &str {
+        "ascii"
+    }
+
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_ascii(floors, name, &params.file_path, &params.ascii)
+    }
+}
+
+struct Csv;
+
+impl Exporter for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_csv(floors, name, &params.file_path, &params.csv)
+    }
+}
+
+struct Svg;
+
+impl Exporter for Svg {
+    fn name(&self) -> &str {
+        "svg"
+    }
+
+    fn extension(&self) -> &str {
+        "svg"
+    }
+
+    fn write_dungeon(
+        &self,
+        floors: &[Dungeon],
+        name: &str,
+        params: &DungeonParameters,
+    ) -> std::io::Result<()> {
+        super::write_dungeons_to_svg(floors, name, &params.file_path)
+    }
+}
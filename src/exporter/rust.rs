@@ -0,0 +1,102 @@
+use std::io::prelude::*;
+
+use crate::generator::{Dungeon, RoomType};
+
+fn tile_enum() -> String {
+    let mut text = String::new();
+    text.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n#[repr(u8)]\npub enum Tile {\n");
+    for room_type in RoomType::ALL {
+        text.push_str(&format!("    {:?} = {},\n", room_type, room_type.to_int()));
+    }
+    text.push_str("}\n\nimpl Tile {\n    pub const fn from_u8(value: u8) -> Option<Tile> {\n        match value {\n");
+    for room_type in RoomType::ALL {
+        text.push_str(&format!(
+            "            {} => Some(Tile::{:?}),\n",
+            room_type.to_int(),
+            room_type
+        ));
+    }
+    text.push_str("            _ => None,\n        }\n    }\n}\n");
+    text
+}
+
+fn points(points: impl Iterator<Item = (u32, u32)>) -> String {
+    points
+        .map(|(x, y)| format!("({}, {})", x, y))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Writes <name>.rs with the floors as tile id arrays, row by row, and their
+// sizes, start and end tiles as constants. Meant to be include!d into a module
+// of its own, every dungeon uses the same names.
+pub fn write_dungeons_to_rust(data: &[Dungeon], name: &str, filename: &str) -> std::io::Result<()> {
+    let mut text = String::new();
+    text.push_str(&format!(
+        "// Generated by dungeonsprawler from {}\n\n",
+        name
+    ));
+    text.push_str(&tile_enum());
+
+    text.push_str(&format!(
+        "\npub const FLOOR_COUNT: usize = {};\n",
+        data.len()
+    ));
+    if let Some(d) = data.first() {
+        text.push_str(&format!("pub const WIDTH: usize = {};\n", d.width));
+        text.push_str(&format!("pub const HEIGHT: usize = {};\n", d.height));
+    }
+    text.push_str(&format!(
+        "// Per floor, (width, height) and (x, y) tiles\npub const SIZES: [(usize, usize); {}] = [{}];\n",
+        data.len(),
+        points(data.iter().map(|d| (d.width, d.height)))
+    ));
+    text.push_str(&format!(
+        "pub const STARTS: [(usize, usize); {}] = [{}];\n",
+        data.len(),
+        points(data.iter().map(|d| (d.start_x, d.start_y)))
+    ));
+    text.push_str(&format!(
+        "pub const ENDS: [(usize, usize); {}] = [{}];\n",
+        data.len(),
+        points(data.iter().map(|d| (d.end_x, d.end_y)))
+    ));
+
+    for (i, d) in data.iter().enumerate() {
+        let rows = d
+            .grid
+            .iter()
+            .map(|row| {
+                let row = row
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("    {},", row)
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        text.push_str(&format!(
+            "\n#[rustfmt::skip]\npub const FLOOR{}: [u8; {}] = [\n{}\n];\n",
+            i,
+            d.width * d.height,
+            rows
+        ));
+    }
+
+    let floors = (0..data.len())
+        .map(|i| format!("&FLOOR{}", i))
+        .collect::<Vec<String>>()
+        .join(", ");
+    text.push_str(&format!(
+        "\npub const FLOORS: [&[u8]; {}] = [{}];\n",
+        data.len(),
+        floors
+    ));
+
+    let filename = format!("{}{}.rs", filename, name);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(text.as_bytes())?;
+
+    Ok(())
+}
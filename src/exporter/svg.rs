@@ -0,0 +1,138 @@
+use std::io::prelude::*;
+
+use crate::generator::stats;
+use crate::generator::{Dungeon, RoomType};
+
+const TILE_SIZE: u32 = 16;
+
+// A rect per run of the same tile in a row, keeps the file small and a one
+// tile change a one line diff
+fn tiles(d: &Dungeon) -> String {
+    let mut text = String::new();
+    for (y, row) in d.grid.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let tile = row[x];
+            let run = row[x..].iter().take_while(|&&t| t == tile).count();
+            text.push_str(&format!(
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x as u32 * TILE_SIZE,
+                y as u32 * TILE_SIZE,
+                run as u32 * TILE_SIZE,
+                TILE_SIZE,
+                super::hex_color(tile)
+            ));
+            x += run;
+        }
+    }
+    text
+}
+
+// The inside of every room, without its wall ring, as x, y, width and height
+fn room_rects(d: &Dungeon) -> Vec<(u32, u32, u32, u32, RoomType)> {
+    d.placed_rooms()
+        .iter()
+        .map(|room| {
+            (
+                (room.x + 1) * TILE_SIZE,
+                (room.y + 1) * TILE_SIZE,
+                (room.width - 2) * TILE_SIZE,
+                (room.height - 2) * TILE_SIZE,
+                room.room_type,
+            )
+        })
+        .collect()
+}
+
+// A bar across the tile along the wall the door sits in
+fn doors(d: &Dungeon) -> String {
+    let mut text = String::new();
+    for (x, y, orientation) in d.doors() {
+        let (x, y) = (x * TILE_SIZE, y * TILE_SIZE);
+        let (x1, y1, x2, y2) = if orientation == RoomType::LockedDoor {
+            (x, y + TILE_SIZE / 2, x + TILE_SIZE, y + TILE_SIZE / 2)
+        } else {
+            (x + TILE_SIZE / 2, y, x + TILE_SIZE / 2, y + TILE_SIZE)
+        };
+        text.push_str(&format!(
+            "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n",
+            x1, y1, x2, y2
+        ));
+    }
+    text
+}
+
+// The floor as an SVG document, 16 units per tile: tiles in the painter's
+// colours, room outlines and labels, door markers and the critical path from
+// start to end, each in a group of its own
+pub fn render_svg(d: &Dungeon) -> String {
+    let (width, height) = (d.width * TILE_SIZE, d.height * TILE_SIZE);
+    let mut text = String::new();
+    text.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    ));
+
+    text.push_str("  <g id=\"tiles\" shape-rendering=\"crispEdges\">\n");
+    text.push_str(&tiles(d));
+    text.push_str("  </g>\n");
+
+    text.push_str("  <g id=\"rooms\" fill=\"none\" stroke=\"#FFFFFF\" stroke-width=\"2\">\n");
+    for (x, y, width, height, _) in room_rects(d) {
+        text.push_str(&format!(
+            "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            x, y, width, height
+        ));
+    }
+    text.push_str("  </g>\n");
+
+    text.push_str("  <g id=\"doors\" stroke=\"#0000FF\" stroke-width=\"4\">\n");
+    text.push_str(&doors(d));
+    text.push_str("  </g>\n");
+
+    if let Some(path) = stats::critical_path(d) {
+        let points = path
+            .iter()
+            .map(|(x, y)| {
+                format!(
+                    "{},{}",
+                    x * TILE_SIZE + TILE_SIZE / 2,
+                    y * TILE_SIZE + TILE_SIZE / 2
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        text.push_str(&format!(
+            "  <polyline id=\"critical-path\" points=\"{}\" fill=\"none\" stroke=\"#FF0000\" stroke-width=\"3\" stroke-dasharray=\"6 4\"/>\n",
+            points
+        ));
+    }
+
+    // Room types last so nothing draws over them
+    text.push_str(&format!(
+        "  <g id=\"labels\" fill=\"#FFFFFF\" stroke=\"#000000\" stroke-width=\"3\" paint-order=\"stroke\" font-family=\"monospace\" font-weight=\"bold\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">\n",
+        TILE_SIZE
+    ));
+    for (x, y, width, height, room_type) in room_rects(d) {
+        text.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\">{}</text>\n",
+            x + width / 2,
+            y + height / 2,
+            room_type.name()
+        ));
+    }
+    text.push_str("  </g>\n");
+    text.push_str("</svg>\n");
+    text
+}
+
+// Writes every floor as <name>N.svg
+pub fn write_dungeons_to_svg(data: &[Dungeon], name: &str, filename: &str) -> std::io::Result<()> {
+    for (i, d) in data.iter().enumerate() {
+        let filename = format!("{}{}{}.svg", filename, name, i);
+        let mut file = super::create_output_file(&filename)?;
+        file.write_all(render_svg(d).as_bytes())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::io::prelude::*;
+
+use crate::generator::paramaters::Tic80Options;
+use crate::generator::Dungeon;
+
+const MAP_WIDTH: u32 = 240;
+const MAP_HEIGHT: u32 = 136;
+
+// Cart chunk types
+const CHUNK_MAP: u8 = 4;
+const CHUNK_CODE: u8 = 5;
+// Empty chunk asking for the default palette, waveforms and sfx
+const CHUNK_DEFAULT: u8 = 17;
+
+// Type in the low 5 bits and bank 0 in the top 3, little endian size, then a
+// reserved byte
+fn chunk(chunk_type: u8, data: &[u8]) -> Vec<u8> {
+    let size = data.len() as u16;
+    let mut bytes = vec![chunk_type, size as u8, (size >> 8) as u8, 0];
+    bytes.extend(data);
+    bytes
+}
+
+// A line for every floor the map leaves out
+pub fn skipped_floors(data: &[Dungeon], name: &str) -> Vec<String> {
+    super::fantasy_console_notes(data, name, (MAP_WIDTH, MAP_HEIGHT), "tic-80")
+}
+
+// Writes every floor that fits into the 240x136 TIC-80 map, as a <name>.tic
+// cart with a `floors` lua table of where each one went in its code, or when
+// `cart` is false as the raw <name>.map the map editor imports.
+pub fn write_dungeons_to_tic80(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    options: &Tic80Options,
+    cart: bool,
+) -> std::io::Result<()> {
+    let (map, floors) =
+        super::fantasy_console_map(data, (MAP_WIDTH, MAP_HEIGHT), &options.tile_map);
+    let map: Vec<u8> = map.into_iter().flatten().collect();
+
+    let (extension, bytes) = if cart {
+        let mut bytes = chunk(CHUNK_DEFAULT, &[]);
+        bytes.extend(chunk(CHUNK_MAP, &map));
+        bytes.extend(chunk(CHUNK_CODE, floors.as_bytes()));
+        ("tic", bytes)
+    } else {
+        ("map", map)
+    };
+
+    let filename = format!("{}{}.{}", filename, name, extension);
+    let mut file = super::create_output_file(&filename)?;
+    file.write_all(&bytes)?;
+
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use serde_json::json;
+use std::io::prelude::*;
+
+use crate::generator::painter;
+use crate::generator::{Dungeon, RoomType};
+
+const TILE_SIZE: u32 = 8;
+
+struct MapObject {
+    name: String,
+    kind: &'static str,
+    x: u32,
+    y: u32,
+    // None for point objects
+    size: Option<(u32, u32)>,
+}
+
+// Rooms as rectangles around their floor, start, end and doors as points in
+// the middle of their tile
+fn map_objects(d: &Dungeon) -> Vec<MapObject> {
+    let mut objects: Vec<MapObject> = Vec::new();
+    let center = |x: u32, y: u32| (x * TILE_SIZE + TILE_SIZE / 2, y * TILE_SIZE + TILE_SIZE / 2);
+
+    for room in d.placed_rooms() {
+        if room.room_type == RoomType::Start || room.room_type == RoomType::End {
+            continue;
+        }
+        objects.push(MapObject {
+            name: room.room_type.name().to_string(),
+            kind: "room",
+            x: (room.x + 1) * TILE_SIZE,
+            y: (room.y + 1) * TILE_SIZE,
+            size: Some(((room.width - 2) * TILE_SIZE, (room.height - 2) * TILE_SIZE)),
+        });
+    }
+
+    let (x, y) = center(d.start_x, d.start_y);
+    objects.push(MapObject {
+        name: "start".to_string(),
+        kind: "start",
+        x,
+        y,
+        size: None,
+    });
+    let (x, y) = center(d.end_x, d.end_y);
+    objects.push(MapObject {
+        name: "end".to_string(),
+        kind: "end",
+        x,
+        y,
+        size: None,
+    });
+
+    for (door_x, door_y, orientation) in d.doors() {
+        let (x, y) = center(door_x, door_y);
+        objects.push(MapObject {
+            name: if orientation == RoomType::LockedDoor {
+                "horizontal".to_string()
+            } else {
+                "vertical".to_string()
+            },
+            kind: "door",
+            x,
+            y,
+            size: None,
+        });
+    }
+
+    objects
+}
+
+// Tiled gids start at 1, 0 means no tile
+fn gids(d: &Dungeon) -> Vec<u32> {
+    d.grid.iter().flatten().map(|&x| x as u32 + 1).collect()
+}
+
+fn tmx(d: &Dungeon, name: &str, tileset: &str) -> String {
+    let objects = map_objects(d);
+    let tile_count = RoomType::ALL.len() as u32;
+
+    let mut text = String::new();
+    text.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    text.push_str(&format!(
+        "<map version=\"1.8\" tiledversion=\"1.8.0\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"3\" nextobjectid=\"{}\">\n",
+        d.width,
+        d.height,
+        TILE_SIZE,
+        TILE_SIZE,
+        objects.len() + 1
+    ));
+    text.push_str(&format!(
+        " <tileset firstgid=\"1\" name=\"{}_tiles\" tilewidth=\"{}\" tileheight=\"{}\" tilecount=\"{}\" columns=\"{}\">\n",
+        name, TILE_SIZE, TILE_SIZE, tile_count, tile_count
+    ));
+    text.push_str(&format!(
+        "  <image source=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+        tileset,
+        tile_count * TILE_SIZE,
+        TILE_SIZE
+    ));
+    for room_type in RoomType::ALL {
+        text.push_str(&format!(
+            "  <tile id=\"{}\" type=\"{}\"/>\n",
+            room_type.to_int(),
+            room_type.name()
+        ));
+    }
+    text.push_str(" </tileset>\n");
+
+    let rows = super::rows_text(
+        &gids(d)
+            .chunks(d.width as usize)
+            .map(|row| row.to_vec())
+            .collect::<Vec<Vec<u32>>>(),
+    );
+    text.push_str(&format!(
+        " <layer id=\"1\" name=\"tiles\" width=\"{}\" height=\"{}\">\n  <data encoding=\"csv\">\n{}\n</data>\n </layer>\n",
+        d.width, d.height, rows
+    ));
+
+    text.push_str(" <objectgroup id=\"2\" name=\"rooms\">\n");
+    for (i, object) in objects.iter().enumerate() {
+        match object.size {
+            Some((width, height)) => text.push_str(&format!(
+                "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                i + 1,
+                object.name,
+                object.kind,
+                object.x,
+                object.y,
+                width,
+                height
+            )),
+            None => text.push_str(&format!(
+                "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\">\n   <point/>\n  </object>\n",
+                i + 1,
+                object.name,
+                object.kind,
+                object.x,
+                object.y
+            )),
+        }
+    }
+    text.push_str(" </objectgroup>\n</map>\n");
+
+    text
+}
+
+fn tmj(d: &Dungeon, name: &str, tileset: &str) -> serde_json::Value {
+    let objects = map_objects(d);
+    let tile_count = RoomType::ALL.len() as u32;
+
+    let objects: Vec<serde_json::Value> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| {
+            let (width, height) = object.size.unwrap_or((0, 0));
+            json!({
+                "id": i + 1,
+                "name": object.name,
+                "type": object.kind,
+                "x": object.x,
+                "y": object.y,
+                "width": width,
+                "height": height,
+                "point": object.size.is_none(),
+                "rotation": 0,
+                "visible": true,
+            })
+        })
+        .collect();
+
+    let tiles: Vec<serde_json::Value> = RoomType::ALL
+        .iter()
+        .map(|room_type| json!({ "id": room_type.to_int(), "type": room_type.name() }))
+        .collect();
+
+    json!({
+        "type": "map",
+        "version": "1.8",
+        "tiledversion": "1.8.0",
+        "orientation": "orthogonal",
+        "renderorder": "right-down",
+        "width": d.width,
+        "height": d.height,
+        "tilewidth": TILE_SIZE,
+        "tileheight": TILE_SIZE,
+        "infinite": false,
+        "nextlayerid": 3,
+        "nextobjectid": objects.len() + 1,
+        "tilesets": [{
+            "firstgid": 1,
+            "name": format!("{}_tiles", name),
+            "image": tileset,
+            "imagewidth": tile_count * TILE_SIZE,
+            "imageheight": TILE_SIZE,
+            "tilewidth": TILE_SIZE,
+            "tileheight": TILE_SIZE,
+            "tilecount": tile_count,
+            "columns": tile_count,
+            "margin": 0,
+            "spacing": 0,
+            "tiles": tiles,
+        }],
+        "layers": [
+            {
+                "type": "tilelayer",
+                "id": 1,
+                "name": "tiles",
+                "width": d.width,
+                "height": d.height,
+                "x": 0,
+                "y": 0,
+                "opacity": 1,
+                "visible": true,
+                "data": gids(d),
+            },
+            {
+                "type": "objectgroup",
+                "id": 2,
+                "name": "rooms",
+                "draworder": "topdown",
+                "x": 0,
+                "y": 0,
+                "opacity": 1,
+                "visible": true,
+                "objects": objects,
+            }
+        ],
+    })
+}
+
+// Writes <name>N.tmx (or .tmj when json is set) for every floor and the
+// <name>_tileset.png they share, coloured like the png previews.
+pub fn write_dungeons_to_tiled(
+    data: &[Dungeon],
+    name: &str,
+    filename: &str,
+    json: bool,
+) -> std::io::Result<()> {
+    let tileset = format!("{}_tileset.png", name);
+    let tileset_path = format!("{}{}", filename, tileset);
+    super::create_parent_dirs(&tileset_path)?;
+    painter::paint_tileset(RoomType::ALL.len() as u32, TILE_SIZE, &tileset_path)?;
+
+    for (i, d) in data.iter().enumerate() {
+        let (extension, text) = if json {
+            (
+                "tmj",
+                serde_json::to_string_pretty(&tmj(d, name, &tileset))?,
+            )
+        } else {
+            ("tmx", tmx(d, name, &tileset))
+        };
+
+        let mut file =
+            super::create_output_file(&format!("{}{}{}.{}", filename, name, i, extension))?;
+        file.write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
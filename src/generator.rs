@@ -1,12 +1,16 @@
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use std::collections::{HashMap, HashSet};
 
-mod painter;
+pub mod analysis;
+mod autotile;
+pub mod painter;
 pub mod paramaters;
+pub mod stats;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum RoomType {
+pub enum RoomType {
     Empty,
     Wall,
     Hall,
@@ -17,10 +21,33 @@ enum RoomType {
     Treasure,
     Secret,
     LockedDoor,
+    // Only used by the raycast grid, LockedDoor doubles as the horizontal door there
+    LockedDoorVertical,
 }
 
 impl RoomType {
-    fn to_int(&self) -> u32 {
+    pub const ALL: [RoomType; 11] = [
+        RoomType::Empty,
+        RoomType::Wall,
+        RoomType::Hall,
+        RoomType::Start,
+        RoomType::End,
+        RoomType::Boss,
+        RoomType::Shop,
+        RoomType::Treasure,
+        RoomType::Secret,
+        RoomType::LockedDoor,
+        RoomType::LockedDoorVertical,
+    ];
+
+    fn from_int(value: i32) -> Option<RoomType> {
+        RoomType::ALL
+            .iter()
+            .find(|t| t.to_int() as i32 == value)
+            .cloned()
+    }
+
+    pub fn to_int(&self) -> u32 {
         match self {
             RoomType::Empty => 0,
             RoomType::Wall => 1,
@@ -32,37 +59,83 @@ impl RoomType {
             RoomType::Treasure => 7,
             RoomType::Secret => 8,
             RoomType::LockedDoor => 9,
+            RoomType::LockedDoorVertical => 10,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<RoomType> {
+        match name {
+            "start" => Some(RoomType::Start),
+            "end" => Some(RoomType::End),
+            "boss" => Some(RoomType::Boss),
+            "shop" => Some(RoomType::Shop),
+            "treasure" => Some(RoomType::Treasure),
+            "secret" => Some(RoomType::Secret),
+            _ => None,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoomType::Empty => "empty",
+            RoomType::Wall => "wall",
+            RoomType::Hall => "hall",
+            RoomType::Start => "start",
+            RoomType::End => "end",
+            RoomType::Boss => "boss",
+            RoomType::Shop => "shop",
+            RoomType::Treasure => "treasure",
+            RoomType::Secret => "secret",
+            RoomType::LockedDoor => "locked_door",
+            RoomType::LockedDoorVertical => "locked_door_vertical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Grid,
+    Raycast,
+    Autotile,
 }
 
+// Placed rooms include their wall ring in x, y, width and height
 #[derive(Debug, Clone, Copy)]
-struct Room {
-    height: u32,
-    width: u32,
-    x: u32,
-    y: u32,
-    room_type: RoomType,
+pub struct Room {
+    pub height: u32,
+    pub width: u32,
+    pub x: u32,
+    pub y: u32,
+    pub room_type: RoomType,
 }
 
 pub struct Dungeon {
     rooms: Vec<Room>,
     placed_rooms: Vec<Room>,
+    // Rooms that could not find a free spot during generate
+    dropped_rooms: Vec<Room>,
     pub height: u32,
     pub width: u32,
     pub grid: Vec<Vec<i32>>,
     pub raycast_grid: Vec<Vec<i32>>,
+    // Wall masks or remapped wall ids, empty unless autotiling is enabled
+    pub autotile_grid: Vec<Vec<i32>>,
     pub start_x: u32,
     pub start_y: u32,
+    pub end_x: u32,
+    pub end_y: u32,
+    pub stats: stats::FloorStats,
+    // Generating again from this seed with the same options gives the same
+    // floor, None for floors read back from a map
+    pub seed: Option<u64>,
+    rng: StdRng,
     regions_count: i32,
     regions: Vec<Vec<u32>>,
     painter: painter::Painter,
 }
 
 impl Dungeon {
-    fn new(parameters: &paramaters::DungeonOptions) -> Dungeon {
-        let width = parameters.width;
-        let height = parameters.height;
+    fn new(width: u32, height: u32, seed: u64) -> Dungeon {
         let rooms = Vec::new();
         let grid = vec![vec![0; width as usize]; height as usize];
         let raycast_grid = vec![vec![0; width as usize]; height as usize];
@@ -72,34 +145,174 @@ impl Dungeon {
         Dungeon {
             rooms,
             placed_rooms: Vec::new(),
+            dropped_rooms: Vec::new(),
             height,
             width,
             grid,
             raycast_grid,
+            autotile_grid: Vec::new(),
             regions_count: 0,
             regions,
             start_x: 0,
             start_y: 0,
+            end_x: 0,
+            end_y: 0,
+            stats: stats::FloorStats::default(),
+            seed: Some(seed),
+            rng: StdRng::seed_from_u64(seed),
             painter: painter::Painter::new(),
         }
     }
 
+    // Rebuilds a floor from its tile ids, e.g. one read back from an export.
+    // The end tile and rooms are found on the grid, rooms that were asked for
+    // but dropped are lost.
+    pub fn from_grid(grid: Vec<Vec<i32>>, start_x: u32, start_y: u32) -> Result<Dungeon, String> {
+        let height = grid.len() as u32;
+        let width = grid.first().map_or(0, |row| row.len()) as u32;
+        if width == 0 || grid.iter().any(|row| row.len() as u32 != width) {
+            return Err("map rows must all have the same, non zero, length".to_string());
+        }
+        if start_x >= width || start_y >= height {
+            return Err(format!(
+                "start {},{} is outside the {}x{} map",
+                start_x, start_y, width, height
+            ));
+        }
+
+        let mut d = Dungeon::new(width, height, 0);
+        d.seed = None;
+        d.grid = grid;
+        d.start_x = start_x;
+        d.start_y = start_y;
+        (d.end_x, d.end_y) = d
+            .find_tiles(RoomType::End)
+            .first()
+            .cloned()
+            .ok_or("map has no end tile")?;
+        d.placed_rooms = d.find_rooms();
+        d.stats = stats::FloorStats::new(&d, 0);
+
+        d.convert_rooms_to_raycast_format(&HashMap::new());
+        d.reverse_grid_rows();
+
+        Ok(d)
+    }
+
+    fn find_tiles(&self, room_type: RoomType) -> Vec<(u32, u32)> {
+        let mut tiles: Vec<(u32, u32)> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.grid[y as usize][x as usize] == room_type.to_int() as i32 {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles
+    }
+
+    // Every patch of room tiles becomes a room with its wall ring around it,
+    // start and end first like place_start_and_end leaves them.
+    fn find_rooms(&self) -> Vec<Room> {
+        let mut rooms: Vec<Room> = Vec::new();
+        let mut seen = vec![vec![false; self.width as usize]; self.height as usize];
+        for room_type in [
+            RoomType::Start,
+            RoomType::End,
+            RoomType::Boss,
+            RoomType::Shop,
+            RoomType::Treasure,
+            RoomType::Secret,
+        ] {
+            for (x, y) in self.find_tiles(room_type) {
+                if seen[y as usize][x as usize] {
+                    continue;
+                }
+                seen[y as usize][x as usize] = true;
+
+                let (mut min_x, mut min_y, mut max_x, mut max_y) = (x, y, x, y);
+                let mut stack = vec![(x, y)];
+                while let Some((x, y)) = stack.pop() {
+                    min_x = min_x.min(x);
+                    min_y = min_y.min(y);
+                    max_x = max_x.max(x);
+                    max_y = max_y.max(y);
+                    for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                        let nx = x as i32 + dx;
+                        let ny = y as i32 + dy;
+                        if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                            continue;
+                        }
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        if !seen[ny][nx] && self.grid[ny][nx] == room_type.to_int() as i32 {
+                            seen[ny][nx] = true;
+                            stack.push((nx as u32, ny as u32));
+                        }
+                    }
+                }
+
+                // Rooms touching the map edge have no wall ring on that side
+                let x = min_x.saturating_sub(1);
+                let y = min_y.saturating_sub(1);
+                rooms.push(Room {
+                    height: (max_y + 2).min(self.height) - y,
+                    width: (max_x + 2).min(self.width) - x,
+                    x,
+                    y,
+                    room_type,
+                });
+            }
+        }
+        rooms
+    }
+
+    // The raycast grid is stored mirrored left to right for the raycaster, this
+    // is it the same way round as grid
+    pub fn unmirrored_raycast_grid(&self) -> Vec<Vec<i32>> {
+        mirror_rows(&self.raycast_grid)
+    }
+
+    pub fn layer(&self, layer: Layer) -> &Vec<Vec<i32>> {
+        match layer {
+            Layer::Grid => &self.grid,
+            Layer::Raycast => &self.raycast_grid,
+            Layer::Autotile => &self.autotile_grid,
+        }
+    }
+
+    // Start and end come first
+    pub fn placed_rooms(&self) -> &[Room] {
+        &self.placed_rooms
+    }
+
+    // Locked door tiles as (x, y, LockedDoor or LockedDoorVertical)
+    pub fn doors(&self) -> Vec<(u32, u32, RoomType)> {
+        let mut doors: Vec<(u32, u32, RoomType)> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.grid[y as usize][x as usize] == RoomType::LockedDoor.to_int() as i32 {
+                    doors.push((x, y, self.door_orientation(x, y)));
+                }
+            }
+        }
+        doors
+    }
+
     fn add_room(&mut self, room: Room) {
         self.rooms.push(room);
     }
 
     fn place_start_and_end(&mut self) {
-        let mut rng = rand::thread_rng();
         let max_distance = (self.height.max(self.width) / 2) as i32;
         let mut valid_placement = false;
         let attempts = 20;
         let mut trys = 0;
         while !valid_placement && trys < attempts {
             trys += 1;
-            let start_x = rng.gen_range(4..self.width - 4);
-            let start_y = rng.gen_range(4..self.height - 4);
-            let end_x = rng.gen_range(1..self.width - 1);
-            let end_y = rng.gen_range(1..self.height - 1);
+            let start_x = self.rng.gen_range(4..self.width - 4);
+            let start_y = self.rng.gen_range(4..self.height - 4);
+            let end_x = self.rng.gen_range(1..self.width - 1);
+            let end_y = self.rng.gen_range(1..self.height - 1);
             let x: i32 = (start_x as i32 - end_x as i32) as i32;
             let y = (start_y as i32 - end_y as i32) as i32;
             let distance = (x.abs() + y.abs()) as i32;
@@ -114,6 +327,8 @@ impl Dungeon {
 
                 self.start_x = start_x;
                 self.start_y = start_y;
+                self.end_x = end_x;
+                self.end_y = end_y;
 
                 let placed_new = Room {
                     height: 3,
@@ -140,8 +355,6 @@ impl Dungeon {
     }
 
     fn generate(&mut self, buffer: u32) {
-        let mut rng = rand::thread_rng();
-
         for room in &self.rooms {
             let room_type = room.room_type.to_int();
             let mut valid_placement = false;
@@ -152,8 +365,8 @@ impl Dungeon {
             let gen_height = self.height - room.height - buffer;
             while !valid_placement && trys < attempts && gen_width > buffer && gen_height > buffer {
                 trys += 1;
-                let x = rng.gen_range(buffer..gen_width);
-                let y = rng.gen_range(buffer..gen_height);
+                let x = self.rng.gen_range(buffer..gen_width);
+                let y = self.rng.gen_range(buffer..gen_height);
 
                 let mut overlap = false;
                 for i in 0..room.height + (buffer * 2) {
@@ -206,6 +419,10 @@ impl Dungeon {
                     self.painter.add_step(self.grid.clone());
                 }
             }
+
+            if !valid_placement {
+                self.dropped_rooms.push(*room);
+            }
         }
     }
 
@@ -255,7 +472,6 @@ impl Dungeon {
     }
 
     fn make_halls(&mut self, start: (u32, u32)) {
-        let mut rng = rand::thread_rng();
         let mut cells: Vec<(u32, u32)> = Vec::new();
         let dirs = vec![(0, 1), (0, -1), (1, 0), (-1, 0)];
 
@@ -306,10 +522,10 @@ impl Dungeon {
             if valid_halls.len() > 0 {
                 let next_dr: (i32, i32);
 
-                if valid_halls.contains(&last_dr) && rng.gen_ratio(2, 4) {
+                if valid_halls.contains(&last_dr) && self.rng.gen_ratio(2, 4) {
                     next_dr = last_dr;
                 } else {
-                    let random_spot = rng.gen_range(0..valid_halls.len());
+                    let random_spot = self.rng.gen_range(0..valid_halls.len());
                     next_dr = valid_halls[random_spot];
                 }
                 let mut next_cell = (
@@ -398,6 +614,8 @@ impl Dungeon {
         }
 
         let mut connectors: Vec<(u32, u32)> = connector_regions.keys().cloned().collect();
+        // HashMap order changes between runs, sort so a seed always picks the same connectors
+        connectors.sort();
 
         // Keep track of which regions have been merged. This maps an original
         // region index to the one it has been merged to.
@@ -408,8 +626,6 @@ impl Dungeon {
             open_regions.insert(i);
         }
 
-        let mut rng = rand::thread_rng();
-
         //println!("Open regions: {:?}", open_regions);
         while open_regions.len() > 1 && connectors.len() > 0 {
             //self.regions_count += 1;
@@ -417,7 +633,7 @@ impl Dungeon {
             let start = open_regions.len();
 
             // pick a random connector
-            let connector_index = rng.gen_range(0..connectors.len());
+            let connector_index = self.rng.gen_range(0..connectors.len());
             let (x, y) = connectors[connector_index];
 
             let region_types = connector_types.get(&(x, y)).unwrap();
@@ -489,7 +705,7 @@ impl Dungeon {
                 //println!("removing");
                 // This connector isn't needed, but connect it occasionally so that the
                 // dungeon isn't singly-connected.
-                if !locked_door && rng.gen_ratio(1, 5) {
+                if !locked_door && self.rng.gen_ratio(1, 5) {
                     //  println!("----- ADDING EXTRA");
                     self.grid[v.1 as usize][v.0 as usize] = RoomType::Hall.to_int() as i32;
                     self.regions[v.1 as usize][v.0 as usize] = self.regions_count as u32;
@@ -609,28 +825,29 @@ impl Dungeon {
         }
     }
 
-    fn print(&self) {
-        for row in &self.grid {
-            for cell in row {
-                print!("{},", cell);
-            }
-            println!();
-        }
+    fn reverse_grid_rows(&mut self) {
+        self.raycast_grid = mirror_rows(&self.raycast_grid);
     }
 
-    fn reverse_grid_rows(&mut self) {
-        let mut new_grid: Vec<Vec<i32>> = Vec::new();
-        for row in self.raycast_grid.iter() {
-            let mut new_row: Vec<i32> = Vec::new();
-            for cell in row.iter().rev() {
-                new_row.push(*cell);
-            }
-            new_grid.push(new_row.clone());
+    // A door with walls to its left and right sits in a horizontal wall and is
+    // walked through north to south, otherwise it sits in a vertical wall.
+    fn door_orientation(&self, x: u32, y: u32) -> RoomType {
+        let is_wall = |x: i32, y: i32| {
+            x < 0
+                || y < 0
+                || x >= self.width as i32
+                || y >= self.height as i32
+                || self.grid[y as usize][x as usize] == RoomType::Wall.to_int() as i32
+        };
+
+        if is_wall(x as i32 - 1, y as i32) && is_wall(x as i32 + 1, y as i32) {
+            RoomType::LockedDoor
+        } else {
+            RoomType::LockedDoorVertical
         }
-        self.raycast_grid = new_grid;
     }
 
-    fn convert_rooms_to_raycast_format(&mut self) {
+    fn convert_rooms_to_raycast_format(&mut self, wall_textures: &HashMap<String, Vec<i32>>) {
         self.raycast_grid = self.grid.clone();
         for room in &self.placed_rooms {
             let x = room.x;
@@ -659,11 +876,13 @@ impl Dungeon {
         let height = self.height;
         for i in 0..height {
             for j in 0..width {
-                if self.raycast_grid[i as usize][j as usize] == RoomType::Hall.to_int() as i32
-                    || self.raycast_grid[i as usize][j as usize]
-                        == RoomType::LockedDoor.to_int() as i32
-                {
+                if self.raycast_grid[i as usize][j as usize] == RoomType::Hall.to_int() as i32 {
                     self.raycast_grid[(i) as usize][(j) as usize] = RoomType::Empty as i32;
+                } else if self.raycast_grid[i as usize][j as usize]
+                    == RoomType::LockedDoor.to_int() as i32
+                {
+                    self.raycast_grid[(i) as usize][(j) as usize] =
+                        self.door_orientation(j, i).to_int() as i32;
                 } else if self.raycast_grid[i as usize][j as usize]
                     == RoomType::Wall.to_int() as i32
                 {
@@ -671,6 +890,82 @@ impl Dungeon {
                 }
             }
         }
+
+        // Swap the walls for a random texture from the set of the room type
+        // they belong to, corridor walls use the "hall" set. A texture is
+        // picked once per straight run of wall so each wall reads as one
+        // surface: horizontal runs first, then what is left vertically.
+        let owners: Vec<Vec<Option<RoomType>>> = self
+            .raycast_grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&cell| match RoomType::from_int(cell) {
+                        Some(RoomType::Wall) => Some(RoomType::Hall),
+                        Some(
+                            room_type @ (RoomType::Start
+                            | RoomType::End
+                            | RoomType::Boss
+                            | RoomType::Shop
+                            | RoomType::Treasure
+                            | RoomType::Secret),
+                        ) => Some(room_type),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let (width, height) = (width as usize, height as usize);
+        let mut in_run = vec![vec![false; width]; height];
+        let mut runs: Vec<(RoomType, Vec<(usize, usize)>)> = Vec::new();
+        for y in 0..height {
+            let mut x = 0;
+            while x < width {
+                let Some(owner) = owners[y][x] else {
+                    x += 1;
+                    continue;
+                };
+                let length = (x..width)
+                    .take_while(|&x| owners[y][x] == Some(owner))
+                    .count();
+                if length > 1 {
+                    runs.push((owner, (x..x + length).map(|x| (x, y)).collect()));
+                    for cell in &mut in_run[y][x..x + length] {
+                        *cell = true;
+                    }
+                }
+                x += length;
+            }
+        }
+        for x in 0..width {
+            let mut y = 0;
+            while y < height {
+                let owner = match owners[y][x] {
+                    Some(owner) if !in_run[y][x] => owner,
+                    _ => {
+                        y += 1;
+                        continue;
+                    }
+                };
+                let length = (y..height)
+                    .take_while(|&y| owners[y][x] == Some(owner) && !in_run[y][x])
+                    .count();
+                runs.push((owner, (y..y + length).map(|y| (x, y)).collect()));
+                y += length;
+            }
+        }
+
+        for (owner, cells) in runs {
+            if let Some(&texture) = wall_textures
+                .get(owner.name())
+                .and_then(|textures| textures.choose(&mut self.rng))
+            {
+                for (x, y) in cells {
+                    self.raycast_grid[y][x] = texture;
+                }
+            }
+        }
     }
 }
 
@@ -684,43 +979,25 @@ pub fn connected(d: Dungeon) -> bool {
     d.are_start_and_end_connected()
 }
 
-fn gen_floor(paramaters: &paramaters::DungeonOptions, format: String) -> Option<Dungeon> {
-    let mut d = Dungeon::new(&paramaters);
-    let mut rng = rand::thread_rng();
+// A single floor from its own seed, the same options and seed always give the
+// same floor. None when start and end could not be connected.
+pub fn gen_floor(paramaters: &paramaters::DungeonOptions, seed: u64) -> Option<Dungeon> {
+    let mut d = Dungeon::new(paramaters.width, paramaters.height, seed);
     d.place_start_and_end();
 
     for _ in 0..paramaters.amount_of_rooms {
-        let room_type = paramaters.room_types.choose(&mut rng).unwrap();
-        let mut room = Room {
-            height: rng.gen_range(paramaters.room_size_low..paramaters.room_size_high),
-            width: rng.gen_range(paramaters.room_size_low..paramaters.room_size_high),
+        let room_type = paramaters.room_types.choose(&mut d.rng).unwrap();
+        let room = Room {
+            height: d
+                .rng
+                .gen_range(paramaters.room_size_low..paramaters.room_size_high),
+            width: d
+                .rng
+                .gen_range(paramaters.room_size_low..paramaters.room_size_high),
             x: 0,
             y: 0,
-            room_type: RoomType::Empty,
+            room_type: RoomType::from_name(room_type).unwrap_or(RoomType::Empty),
         };
-        match room_type.as_str() {
-            "start" => {
-                room.room_type = RoomType::Start;
-            }
-            "end" => {
-                room.room_type = RoomType::End;
-            }
-            "boss" => {
-                room.room_type = RoomType::Boss;
-            }
-            "shop" => {
-                room.room_type = RoomType::Shop;
-            }
-            "treasure" => {
-                room.room_type = RoomType::Treasure;
-            }
-            "secret" => {
-                room.room_type = RoomType::Secret;
-            }
-            _ => {
-                room.room_type = RoomType::Empty;
-            }
-        }
         d.add_room(room);
     }
 
@@ -746,23 +1023,79 @@ fn gen_floor(paramaters: &paramaters::DungeonOptions, format: String) -> Option<
     }
     d.painter.paint();
 
-    if format == "raycast" {
-        d.convert_rooms_to_raycast_format();
-        d.reverse_grid_rows();
+    if let Some(options) = &paramaters.autotile {
+        d.autotile_grid = autotile::autotile(&d, options);
     }
 
+    d.convert_rooms_to_raycast_format(&paramaters.wall_textures);
+    d.reverse_grid_rows();
+
     Some(d)
 }
 
-pub fn new_dungeon(paramaters: &paramaters::DungeonParameters) -> Vec<Vec<Dungeon>> {
+// Hands out the seed of every floor attempt, fixed by the dungeon seed if set
+fn floor_seeds(paramaters: &paramaters::DungeonOptions) -> StdRng {
+    match paramaters.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+pub fn new_dungeon(
+    paramaters: &paramaters::DungeonParameters,
+) -> Result<Vec<Vec<Dungeon>>, String> {
     let mut dungeons: Vec<Vec<Dungeon>> = Vec::new();
 
-    for i in 0..paramaters.dungeons.len() {
+    for options in &paramaters.dungeons {
+        if let Some(autotile) = &options.autotile {
+            if autotile.neighbours != 4 && autotile.neighbours != 8 {
+                return Err(format!(
+                    "{}: autotile neighbours must be 4 or 8, got {}",
+                    options.name, autotile.neighbours
+                ));
+            }
+        }
+
+        let mut seeds = floor_seeds(options);
         let mut floors: Vec<Dungeon> = Vec::new();
-        while floors.len() < paramaters.dungeons[i].count as usize {
-            let d = gen_floor(&paramaters.dungeons[i], paramaters.output_format.clone());
-            if d.is_some() {
-                floors.push(d.unwrap());
+        let mut attempts = 0;
+        // Why attempts for the current floor were thrown away
+        let mut failures: HashMap<String, u32> = HashMap::new();
+        while floors.len() < options.count as usize {
+            if attempts >= options.constraints.max_attempts {
+                // Most common reason first
+                let mut failures: Vec<(String, u32)> = failures.drain().collect();
+                failures.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                let reason = failures
+                    .iter()
+                    .map(|(name, count)| format!("{} failed {} times", name, count))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                return Err(format!(
+                    "{}: could not generate floor {} satisfying the constraints in {} attempts: {}",
+                    options.name,
+                    floors.len(),
+                    options.constraints.max_attempts,
+                    reason
+                ));
+            }
+
+            attempts += 1;
+            let seed = options.floor_seed.unwrap_or_else(|| seeds.gen());
+            let d = gen_floor(options, seed);
+            match d {
+                Some(mut d) => {
+                    d.stats = stats::FloorStats::new(&d, attempts);
+                    match d.stats.failed_constraint(&options.constraints) {
+                        Some(failed) => *failures.entry(failed).or_insert(0) += 1,
+                        None => {
+                            floors.push(d);
+                            attempts = 0;
+                            failures.clear();
+                        }
+                    }
+                }
+                None => *failures.entry("floor generation".to_string()).or_insert(0) += 1,
             }
         }
         dungeons.push(floors);
@@ -770,14 +1103,56 @@ pub fn new_dungeon(paramaters: &paramaters::DungeonParameters) -> Vec<Vec<Dungeo
 
     if paramaters.include_images {
         for (i, d) in dungeons.iter().enumerate() {
-            for (j, d) in d.iter().enumerate() {
-                let name = format!("images/dungeon_{}{}.png", paramaters.dungeons[i].name, j);
-                std::fs::create_dir_all(paramaters.file_path.clone() + "images/");
-                let name = paramaters.file_path.clone() + &name;
-                d.painter.paint_image(&d.grid, &name);
+            paint_images(d, &paramaters.dungeons[i].name, &paramaters.file_path);
+        }
+    }
+
+    Ok(dungeons)
+}
+
+// Every row reversed, mirroring the grid left to right
+pub fn mirror_rows(grid: &[Vec<i32>]) -> Vec<Vec<i32>> {
+    grid.iter()
+        .map(|row| row.iter().rev().cloned().collect())
+        .collect()
+}
+
+// Writes images/dungeon_<name>N.png under file_path for every floor
+pub fn paint_images(floors: &[Dungeon], name: &str, file_path: &str) {
+    for (j, d) in floors.iter().enumerate() {
+        let name = format!("images/dungeon_{}{}.png", name, j);
+        let _ = std::fs::create_dir_all(file_path.to_string() + "images/");
+        let name = file_path.to_string() + &name;
+        d.painter.paint_image(&d.grid, &name);
+    }
+}
+
+// Runs gen_floor `samples` times per dungeon spec without writing anything and
+// reports how the resulting floors are distributed.
+pub fn analyze(paramaters: &paramaters::DungeonParameters, samples: u32) -> Vec<analysis::Report> {
+    let mut reports: Vec<analysis::Report> = Vec::new();
+
+    for options in &paramaters.dungeons {
+        let mut seeds = floor_seeds(options);
+        let mut floors: Vec<stats::FloorStats> = Vec::new();
+        let mut rejected = 0;
+        for _ in 0..samples {
+            if let Some(d) = gen_floor(options, seeds.gen()) {
+                let floor = stats::FloorStats::new(&d, 1);
+                if floor.satisfies(&options.constraints) {
+                    floors.push(floor);
+                } else {
+                    rejected += 1;
+                }
             }
         }
+        reports.push(analysis::Report::new(
+            &options.name,
+            samples,
+            rejected,
+            &floors,
+        ));
     }
 
-    dungeons
+    reports
 }
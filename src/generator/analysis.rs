@@ -0,0 +1,126 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::stats::FloorStats;
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Distribution {
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    fn new(values: &[f64]) -> Distribution {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        Distribution {
+            min: values.iter().cloned().fold(f64::INFINITY, f64::min),
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            max: values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "min {:>7.2}  mean {:>7.2}  max {:>7.2}",
+            self.min, self.mean, self.max
+        )
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Report {
+    pub name: String,
+    pub samples: u32,
+    // Floors gen_floor could not produce plus floors rejected by the constraints
+    pub failures: u32,
+    pub rejected: u32,
+    pub failure_rate: f64,
+    pub walkable_ratio: Distribution,
+    pub rooms_placed: Distribution,
+    pub rooms_dropped: Distribution,
+    pub dead_ends: Distribution,
+    pub corridor_length: Distribution,
+    pub cycles: Distribution,
+    pub critical_path_length: Distribution,
+    pub locked_doors: Distribution,
+    // Manhattan distance between start and end -> number of floors
+    pub start_end_distances: BTreeMap<u32, u32>,
+}
+
+impl Report {
+    pub fn new(name: &str, samples: u32, rejected: u32, floors: &[FloorStats]) -> Report {
+        let collect = |f: &dyn Fn(&FloorStats) -> f64| -> Distribution {
+            Distribution::new(&floors.iter().map(f).collect::<Vec<f64>>())
+        };
+
+        let mut start_end_distances: BTreeMap<u32, u32> = BTreeMap::new();
+        for floor in floors {
+            *start_end_distances
+                .entry(floor.start_end_distance)
+                .or_insert(0) += 1;
+        }
+
+        let failures = samples - floors.len() as u32;
+        Report {
+            name: name.to_string(),
+            samples,
+            failures,
+            rejected,
+            failure_rate: if samples > 0 {
+                failures as f64 / samples as f64
+            } else {
+                0.0
+            },
+            walkable_ratio: collect(&|s| s.walkable_ratio),
+            rooms_placed: collect(&|s| s.rooms_placed() as f64),
+            rooms_dropped: collect(&|s| s.rooms_dropped.values().sum::<u32>() as f64),
+            dead_ends: collect(&|s| s.dead_ends as f64),
+            corridor_length: collect(&|s| s.corridor_length as f64),
+            cycles: collect(&|s| s.cycles as f64),
+            critical_path_length: collect(&|s| s.critical_path_length.unwrap_or(0) as f64),
+            locked_doors: collect(&|s| s.locked_doors as f64),
+            start_end_distances,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {} samples", self.name, self.samples)?;
+        writeln!(
+            f,
+            "  failures:       {} ({:.1}%), {} rejected by constraints",
+            self.failures,
+            self.failure_rate * 100.0,
+            self.rejected
+        )?;
+        writeln!(f, "  walkable ratio: {}", self.walkable_ratio)?;
+        writeln!(f, "  rooms placed:   {}", self.rooms_placed)?;
+        writeln!(f, "  rooms dropped:  {}", self.rooms_dropped)?;
+        writeln!(f, "  dead ends:      {}", self.dead_ends)?;
+        writeln!(f, "  corridors:      {}", self.corridor_length)?;
+        writeln!(f, "  cycles:         {}", self.cycles)?;
+        writeln!(f, "  critical path:  {}", self.critical_path_length)?;
+        writeln!(f, "  locked doors:   {}", self.locked_doors)?;
+        write!(f, "  start-end distance:")?;
+
+        let most = self
+            .start_end_distances
+            .values()
+            .max()
+            .cloned()
+            .unwrap_or(0);
+        for (distance, count) in &self.start_end_distances {
+            let bar = "#".repeat(((*count as f64 / most as f64) * 40.0).ceil() as usize);
+            write!(f, "\n  {:>4} | {:<40} {}", distance, bar, count)?;
+        }
+        Ok(())
+    }
+}
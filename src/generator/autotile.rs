@@ -0,0 +1,104 @@
+use super::paramaters::AutotileOptions;
+use super::{Dungeon, RoomType};
+
+// 4 neighbour bits
+const N4: [(i32, i32, i32); 4] = [(0, -1, 1), (1, 0, 2), (0, 1, 4), (-1, 0, 8)];
+
+// 8 neighbour bits, clockwise from north
+const NORTH: i32 = 1;
+const NORTH_EAST: i32 = 2;
+const EAST: i32 = 4;
+const SOUTH_EAST: i32 = 8;
+const SOUTH: i32 = 16;
+const SOUTH_WEST: i32 = 32;
+const WEST: i32 = 64;
+const NORTH_WEST: i32 = 128;
+
+// Builds a layer the size of the grid where every wall holds the bitmask of its
+// wall neighbours, or the tile id the table maps that mask to.
+//
+// Without a table non-wall tiles are 0 and the game reads the normal grid to
+// tell walls apart, with a table non-wall tiles keep their grid id so the
+// layer can be drawn on its own.
+pub fn autotile(d: &Dungeon, options: &AutotileOptions) -> Vec<Vec<i32>> {
+    let wall = RoomType::Wall.to_int() as i32;
+    let mut layer = vec![vec![0; d.width as usize]; d.height as usize];
+
+    for y in 0..d.height as i32 {
+        for x in 0..d.width as i32 {
+            let tile = d.grid[y as usize][x as usize];
+            if tile != wall {
+                if !options.tile_table.is_empty() {
+                    layer[y as usize][x as usize] = tile;
+                }
+                continue;
+            }
+
+            let mask = if options.neighbours == 8 {
+                mask_8(d, x, y)
+            } else {
+                mask_4(d, x, y)
+            };
+            layer[y as usize][x as usize] = if options.tile_table.is_empty() {
+                mask
+            } else {
+                // Masks missing from the table stay plain walls
+                *options.tile_table.get(mask as usize).unwrap_or(&wall)
+            };
+        }
+    }
+
+    layer
+}
+
+// The map edge counts as wall so border walls come out as edges, not ends
+fn is_wall(d: &Dungeon, x: i32, y: i32) -> bool {
+    x < 0
+        || y < 0
+        || x >= d.width as i32
+        || y >= d.height as i32
+        || d.grid[y as usize][x as usize] == RoomType::Wall.to_int() as i32
+}
+
+fn mask_4(d: &Dungeon, x: i32, y: i32) -> i32 {
+    N4.iter()
+        .filter(|(dx, dy, _)| is_wall(d, x + dx, y + dy))
+        .map(|(_, _, bit)| bit)
+        .sum()
+}
+
+// Corners only count when both edges next to them are walls, which folds the
+// 256 raw masks down to the usual 47 distinct tiles.
+fn mask_8(d: &Dungeon, x: i32, y: i32) -> i32 {
+    let n = is_wall(d, x, y - 1);
+    let e = is_wall(d, x + 1, y);
+    let s = is_wall(d, x, y + 1);
+    let w = is_wall(d, x - 1, y);
+
+    let mut mask = 0;
+    if n {
+        mask |= NORTH;
+    }
+    if e {
+        mask |= EAST;
+    }
+    if s {
+        mask |= SOUTH;
+    }
+    if w {
+        mask |= WEST;
+    }
+    if n && e && is_wall(d, x + 1, y - 1) {
+        mask |= NORTH_EAST;
+    }
+    if s && e && is_wall(d, x + 1, y + 1) {
+        mask |= SOUTH_EAST;
+    }
+    if s && w && is_wall(d, x - 1, y + 1) {
+        mask |= SOUTH_WEST;
+    }
+    if n && w && is_wall(d, x - 1, y - 1) {
+        mask |= NORTH_WEST;
+    }
+    mask
+}
@@ -36,7 +36,7 @@ fn create_image_from_values(values: &Vec<Vec<i32>>) -> ImageBuffer<Rgb<u8>, Vec<
     image
 }
 
-fn to_color(value: i32) -> Rgb<u8> {
+pub fn to_color(value: i32) -> Rgb<u8> {
     let color = match value {
         //0 => Rgb([0, 0, 0]), // Black
         0 => Rgb([120, 120, 120]),
@@ -144,6 +144,21 @@ pub fn save_image_to_file(
     Ok(())
 }
 
+// One square tile per id from 0 up to count - 1 in a single row
+pub fn paint_tileset(count: u32, tile_size: u32, file_path: &str) -> Result<(), std::io::Error> {
+    let mut image = ImageBuffer::new(count * tile_size, tile_size);
+    for (x, _, pixel) in image.enumerate_pixels_mut() {
+        *pixel = to_color((x / tile_size) as i32);
+    }
+    save_image_to_file(&image, file_path)
+}
+
+impl Default for Painter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Painter {
     pub fn new() -> Painter {
         Painter {
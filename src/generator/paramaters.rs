@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /* configurable options:
 --------------------------------
@@ -11,11 +12,23 @@ use serde::{Deserialize, Serialize};
 - start / finish min distance
 - room padding / density
 - sprawl or sparse
+- seed / floor seed
+- acceptance constraints
+- wall autotiling
+- raycast wall textures
 
 # Seralization options
 - file path
-- output format (fx, raycast)
+- output formats (fx, raycast, binary, header, tiled, tiled-json, json, ldtk,
+  pico8, tic80, tic80-map, godot, rust, ascii, csv, svg)
 - include images ( based on output format)
+- tile encoding (byte, 2bit, 4bit, palette)
+- compression (none, rle, lz)
+- lua grids (normal, raycast, both)
+- pico-8 and tic-80 tile mapping
+- godot tileset atlas
+- ascii glyphs
+- csv layout
 */
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +45,59 @@ pub struct DungeonOptions {
     pub room_padding_density: u32,
     pub sparse: bool,
     pub count: u32,
+    // Fixes every floor of the dungeon, random when unset
+    pub seed: Option<u64>,
+    // Seed of a single floor, e.g. from the json output, regenerates that floor
+    // for every one of count instead of using seed
+    #[serde(default)]
+    pub floor_seed: Option<u64>,
+    #[serde(default)]
+    pub constraints: FloorConstraints,
+    pub autotile: Option<AutotileOptions>,
+    // Room type name (or "hall" for corridors) -> texture ids for raycast walls
+    #[serde(default)]
+    pub wall_textures: HashMap<String, Vec<i32>>,
+}
+
+// Floors that do not meet these are thrown away and generated again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FloorConstraints {
+    pub min_walkable_percent: Option<f64>,
+    pub min_rooms_placed: Option<u32>,
+    // Reject floors where any room of these types failed to place
+    #[serde(default)]
+    pub required_room_types: Vec<String>,
+    pub max_dead_ends: Option<u32>,
+    pub min_critical_path: Option<u32>,
+    // Give up after this many attempts for a single floor
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_max_attempts() -> u32 {
+    1000
+}
+
+impl Default for FloorConstraints {
+    fn default() -> Self {
+        FloorConstraints {
+            min_walkable_percent: None,
+            min_rooms_placed: None,
+            required_room_types: Vec::new(),
+            max_dead_ends: None,
+            min_critical_path: None,
+            max_attempts: default_max_attempts(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AutotileOptions {
+    // 4 or 8
+    pub neighbours: u32,
+    // Wall mask -> tile id, the raw mask is exported when empty
+    #[serde(default)]
+    pub tile_table: Vec<i32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -41,10 +107,129 @@ pub struct DungeonParameters {
 
     // Serialization options
     pub file_path: String,
+    // Older single format field, kept working alongside output_formats
+    #[serde(default)]
     pub output_format: String,
+    #[serde(default)]
+    pub output_formats: Vec<String>,
     pub include_images: bool,
+    #[serde(default)]
+    pub tile_encoding: TileEncoding,
+    #[serde(default)]
+    pub compression: Compression,
+    #[serde(default)]
+    pub lua_grids: LuaGrids,
+    #[serde(default)]
+    pub pico8: Pico8Options,
+    #[serde(default)]
+    pub tic80: Tic80Options,
+    #[serde(default)]
+    pub godot: GodotOptions,
+    #[serde(default)]
+    pub ascii: AsciiOptions,
+    #[serde(default)]
+    pub csv: CsvOptions,
+}
+
+// How the fx and binary exporters store tiles. Every mode but byte stores
+// indexes into a palette of the tile ids a dungeon uses.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TileEncoding {
+    #[default]
+    #[serde(rename = "byte")]
+    Byte,
+    #[serde(rename = "2bit")]
+    Bits2,
+    #[serde(rename = "4bit")]
+    Bits4,
+    // As few bits as the palette needs
+    #[serde(rename = "palette")]
+    Palette,
+}
+
+impl DungeonParameters {
+    // Every format to write, output_format first and then output_formats
+    // without repeats, fx when neither is set
+    pub fn output_formats(&self) -> Vec<String> {
+        let mut formats: Vec<String> = Vec::new();
+        for format in std::iter::once(&self.output_format).chain(&self.output_formats) {
+            if !format.is_empty() && !formats.contains(format) {
+                formats.push(format.clone());
+            }
+        }
+        if formats.is_empty() {
+            formats.push("fx".to_string());
+        }
+        formats
+    }
 }
 
 pub fn marshal_from_json(json: &str) -> Result<DungeonParameters, serde_json::Error> {
     serde_json::from_str(json)
 }
+
+// Applied to the tile payload of each floor after any bit packing
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Rle,
+    Lz,
+}
+
+// Which grids the raycast lua script writes for every floor
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LuaGrids {
+    Normal,
+    Raycast,
+    #[default]
+    Both,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Pico8Options {
+    // Tile name (wall, hall, boss, ...) -> sprite number, unmapped tiles keep their id
+    #[serde(default)]
+    pub tile_map: HashMap<String, u8>,
+    // Also lay floors out in map rows 32-63, which share memory with sprites 128-255
+    #[serde(default)]
+    pub shared_map: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Tic80Options {
+    // Tile name (wall, hall, boss, ...) -> tile number, unmapped tiles keep their id
+    #[serde(default)]
+    pub tile_map: HashMap<String, u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GodotOptions {
+    // Tile name (wall, hall, boss, ...) -> atlas coordinates, unmapped tiles
+    // use (id, 0) like the generated tileset
+    #[serde(default)]
+    pub atlas: HashMap<String, [u16; 2]>,
+    // Atlas texture path, the generated <name>_tileset.png when unset
+    pub texture: Option<String>,
+    pub tile_size: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AsciiOptions {
+    // Tile name (wall, hall, boss, ...) -> text drawn for it, replacing the
+    // default glyph. Can be more than one character, e.g. "██" for square tiles.
+    #[serde(default)]
+    pub glyphs: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CsvOptions {
+    // All floors in <name>.csv instead of a file per floor
+    #[serde(default)]
+    pub single_file: bool,
+    // Write the raycast grid instead of the normal one
+    #[serde(default)]
+    pub raycast: bool,
+}
@@ -0,0 +1,281 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use super::paramaters::FloorConstraints;
+use super::{Dungeon, Room, RoomType};
+
+const DIRS: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FloorStats {
+    // Rooms actually placed on the floor, keyed by room type name
+    pub rooms: BTreeMap<String, u32>,
+    // Rooms asked for by the spec and the ones that found no free spot
+    pub rooms_requested: BTreeMap<String, u32>,
+    pub rooms_dropped: BTreeMap<String, u32>,
+    pub walkable_tiles: u32,
+    pub walkable_ratio: f64,
+    pub dead_ends: u32,
+    pub corridor_length: u32,
+    pub cycles: u32,
+    // Manhattan distance between the start and end tiles
+    pub start_end_distance: u32,
+    // Shortest walk from start to end, None if they are not connected
+    pub critical_path_length: Option<u32>,
+    pub locked_doors: u32,
+    // How many calls to gen_floor it took to produce this floor
+    pub attempts: u32,
+}
+
+impl FloorStats {
+    pub fn new(d: &Dungeon, attempts: u32) -> FloorStats {
+        let rooms = count_rooms(&d.placed_rooms);
+        let rooms_requested = count_rooms(&d.rooms);
+        let rooms_dropped = count_rooms(&d.dropped_rooms);
+
+        let mut walkable_tiles = 0;
+        let mut dead_ends = 0;
+        let mut corridor_length = 0;
+        let mut locked_doors = 0;
+        for y in 0..d.height {
+            for x in 0..d.width {
+                if !is_walkable(d, x as i32, y as i32) {
+                    continue;
+                }
+                walkable_tiles += 1;
+
+                let exits = DIRS
+                    .iter()
+                    .filter(|(dx, dy)| is_walkable(d, x as i32 + dx, y as i32 + dy))
+                    .count();
+
+                let tile = d.grid[y as usize][x as usize];
+                if tile == RoomType::Hall.to_int() as i32 {
+                    corridor_length += 1;
+                    if exits <= 1 {
+                        dead_ends += 1;
+                    }
+                } else if tile == RoomType::LockedDoor.to_int() as i32 {
+                    locked_doors += 1;
+                }
+            }
+        }
+
+        let total = d.width * d.height;
+        FloorStats {
+            rooms,
+            rooms_requested,
+            rooms_dropped,
+            walkable_tiles,
+            walkable_ratio: if total > 0 {
+                walkable_tiles as f64 / total as f64
+            } else {
+                0.0
+            },
+            dead_ends,
+            corridor_length,
+            cycles: count_cycles(d),
+            start_end_distance: d.start_x.abs_diff(d.end_x) + d.start_y.abs_diff(d.end_y),
+            critical_path_length: critical_path(d).map(|path| path.len() as u32 - 1),
+            locked_doors,
+            attempts,
+        }
+    }
+}
+
+impl FloorStats {
+    // Start and end are always placed, only count the generated rooms
+    pub fn rooms_placed(&self) -> u32 {
+        self.rooms
+            .iter()
+            .filter(|(name, _)| *name != "start" && *name != "end")
+            .map(|(_, count)| *count)
+            .sum()
+    }
+
+    pub fn satisfies(&self, constraints: &FloorConstraints) -> bool {
+        self.failed_constraint(constraints).is_none()
+    }
+
+    // The first constraint this floor does not meet
+    pub fn failed_constraint(&self, constraints: &FloorConstraints) -> Option<String> {
+        if let Some(min) = constraints.min_walkable_percent {
+            if self.walkable_ratio * 100.0 < min {
+                return Some("min_walkable_percent".to_string());
+            }
+        }
+        if let Some(min) = constraints.min_rooms_placed {
+            if self.rooms_placed() < min {
+                return Some("min_rooms_placed".to_string());
+            }
+        }
+        for required in &constraints.required_room_types {
+            if self.rooms_dropped.contains_key(required) {
+                return Some(format!("required_room_types ({})", required));
+            }
+        }
+        if let Some(max) = constraints.max_dead_ends {
+            if self.dead_ends > max {
+                return Some("max_dead_ends".to_string());
+            }
+        }
+        if let Some(min) = constraints.min_critical_path {
+            if self.critical_path_length.unwrap_or(0) < min {
+                return Some("min_critical_path".to_string());
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for FloorStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rooms = format_rooms(&self.rooms);
+        let path = match self.critical_path_length {
+            Some(length) => length.to_string(),
+            None => "unreachable".to_string(),
+        };
+        writeln!(f, "  rooms:          {}", rooms)?;
+        if !self.rooms_dropped.is_empty() {
+            writeln!(
+                f,
+                "  dropped:        {} of {} requested ({})",
+                self.rooms_dropped.values().sum::<u32>(),
+                self.rooms_requested.values().sum::<u32>(),
+                format_rooms(&self.rooms_dropped)
+            )?;
+        }
+        writeln!(
+            f,
+            "  walkable:       {} tiles ({:.1}%)",
+            self.walkable_tiles,
+            self.walkable_ratio * 100.0
+        )?;
+        writeln!(f, "  corridors:      {} tiles", self.corridor_length)?;
+        writeln!(f, "  dead ends:      {}", self.dead_ends)?;
+        writeln!(f, "  cycles:         {}", self.cycles)?;
+        writeln!(f, "  critical path:  {}", path)?;
+        writeln!(f, "  locked doors:   {}", self.locked_doors)?;
+        write!(f, "  attempts:       {}", self.attempts)
+    }
+}
+
+fn count_rooms(rooms: &[Room]) -> BTreeMap<String, u32> {
+    let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+    for room in rooms {
+        *counts.entry(room.room_type.name().to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn format_rooms(rooms: &BTreeMap<String, u32>) -> String {
+    rooms
+        .iter()
+        .map(|(name, count)| format!("{} {}", count, name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn is_walkable(d: &Dungeon, x: i32, y: i32) -> bool {
+    if x < 0 || y < 0 || x >= d.width as i32 || y >= d.height as i32 {
+        return false;
+    }
+    let tile = d.grid[y as usize][x as usize];
+    tile != RoomType::Wall.to_int() as i32 && tile != RoomType::Empty.to_int() as i32
+}
+
+// Room interiors are contracted to a single node so only loops through the
+// corridors count, then the independent cycles are E - V + C.
+fn count_cycles(d: &Dungeon) -> u32 {
+    let node_of = |x: u32, y: u32| -> usize {
+        let tile = d.grid[y as usize][x as usize];
+        for (i, room) in d.placed_rooms.iter().enumerate() {
+            if tile == room.room_type.to_int() as i32
+                && x > room.x
+                && x < room.x + room.width - 1
+                && y > room.y
+                && y < room.y + room.height - 1
+            {
+                return (d.width * d.height) as usize + i;
+            }
+        }
+        (y * d.width + x) as usize
+    };
+
+    let mut nodes: HashSet<usize> = HashSet::new();
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    for y in 0..d.height {
+        for x in 0..d.width {
+            if !is_walkable(d, x as i32, y as i32) {
+                continue;
+            }
+            let node = node_of(x, y);
+            nodes.insert(node);
+            // Each edge is seen from both ends, only look right and down
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if is_walkable(d, nx as i32, ny as i32) {
+                    let other = node_of(nx, ny);
+                    if other != node {
+                        edges.insert((node.min(other), node.max(other)));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut parent: HashMap<usize, usize> = nodes.iter().map(|&n| (n, n)).collect();
+    fn find(parent: &mut HashMap<usize, usize>, n: usize) -> usize {
+        let p = parent[&n];
+        if p == n {
+            return n;
+        }
+        let root = find(parent, p);
+        parent.insert(n, root);
+        root
+    }
+    for &(a, b) in &edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        parent.insert(ra, rb);
+    }
+    let components = nodes.iter().filter(|&&n| find(&mut parent, n) == n).count();
+
+    (edges.len() + components).saturating_sub(nodes.len()) as u32
+}
+
+// Shortest walkable route from the start tile to the end tile, both inclusive
+pub fn critical_path(d: &Dungeon) -> Option<Vec<(u32, u32)>> {
+    let start = (d.start_x as i32, d.start_y as i32);
+    let end = (d.end_x as i32, d.end_y as i32);
+    if !is_walkable(d, start.0, start.1) {
+        return None;
+    }
+
+    let mut previous: Vec<Vec<Option<(i32, i32)>>> =
+        vec![vec![None; d.width as usize]; d.height as usize];
+    previous[start.1 as usize][start.0 as usize] = Some(start);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some((x, y)) = queue.pop_front() {
+        if (x, y) == end {
+            let mut path = vec![(x as u32, y as u32)];
+            let mut cell = (x, y);
+            while cell != start {
+                cell = previous[cell.1 as usize][cell.0 as usize].unwrap();
+                path.push((cell.0 as u32, cell.1 as u32));
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for (dx, dy) in DIRS {
+            let (nx, ny) = (x + dx, y + dy);
+            if is_walkable(d, nx, ny) && previous[ny as usize][nx as usize].is_none() {
+                previous[ny as usize][nx as usize] = Some((x, y));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,420 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::exporter::JSON_SCHEMA_VERSION;
+use crate::generator::{self, Dungeon};
+
+// Reads the dungeons back out of a <name>_maps.txt, <name>_maps.lua or
+// <name>.json written by the exporter, picked by file extension. Lua scripts
+// can hold several dungeons, the other formats hold one.
+pub fn read_dungeons(path: &str) -> std::io::Result<Vec<(String, Vec<Dungeon>)>> {
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("");
+
+    // The wall masks of an autotile layer would be read as tile ids
+    let dungeons = match extension {
+        "txt" if dungeon_name(path).ends_with("_autotile") => Err(
+            "autotile layers can't be imported, import the normal _maps.txt instead".to_string(),
+        ),
+        "txt" => read_maps_txt(&text).map(|floors| vec![(dungeon_name(path), floors)]),
+        "lua" => read_maps_lua(&text),
+        "json" => read_json(&text).map(|dungeon| vec![dungeon]),
+        _ => Err(format!(
+            "don't know how to import .{} files, expected .txt, .lua or .json",
+            extension
+        )),
+    };
+
+    dungeons
+        .and_then(|dungeons| {
+            if dungeons.is_empty() || dungeons.iter().any(|(_, floors)| floors.is_empty()) {
+                Err("no floors found".to_string())
+            } else {
+                Ok(dungeons)
+            }
+        })
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+// Dungeon name the exports were written under, test_dungeon_maps.txt -> test_dungeon
+fn dungeon_name(path: &str) -> String {
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("dungeon");
+    stem.strip_suffix("_maps").unwrap_or(stem).to_string()
+}
+
+fn parse_numbers(text: &str) -> Result<Vec<i32>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            x.parse()
+                .map_err(|_| format!("expected a number, got {}", x))
+        })
+        .collect()
+}
+
+// h, w, two start values, then the tiles row by row
+fn split_floor(numbers: &[i32], floor: usize) -> Result<(usize, Vec<Vec<i32>>), String> {
+    if numbers.len() < 4 {
+        return Err(format!("floor {} is missing its header", floor));
+    }
+    let size = |value: i32| usize::try_from(value).ok();
+    let (height, width) = match (size(numbers[0]), size(numbers[1])) {
+        (Some(height), Some(width)) => (height, width),
+        _ => {
+            return Err(format!(
+                "floor {} has a negative size, {}x{}",
+                floor, numbers[0], numbers[1]
+            ))
+        }
+    };
+    let tiles = &numbers[4..];
+    if width == 0 || width.checked_mul(height) != Some(tiles.len()) {
+        return Err(format!(
+            "floor {} has {} tiles, expected {}x{}, packed or compressed maps can't be imported",
+            floor,
+            tiles.len(),
+            height,
+            width
+        ));
+    }
+    Ok((width, tiles.chunks(width).map(|row| row.to_vec()).collect()))
+}
+
+// A start coordinate from a floor header
+fn start_value(value: i32, floor: usize) -> Result<u32, String> {
+    u32::try_from(value).map_err(|_| format!("floor {} has a negative start, {}", floor, value))
+}
+
+// Extra layers have to line up with the grid, the autotile one may be missing
+fn check_layer(d: &Dungeon, layer: &[Vec<i32>], name: &str, floor: usize) -> Result<(), String> {
+    let lines_up =
+        layer.len() == d.grid.len() && layer.iter().all(|row| row.len() == d.width as usize);
+    if lines_up || (layer.is_empty() && name == "autotile_grid") {
+        Ok(())
+    } else {
+        Err(format!(
+            "floor {} {} does not match the {}x{} grid",
+            floor, name, d.width, d.height
+        ))
+    }
+}
+
+fn read_maps_txt(text: &str) -> Result<Vec<Dungeon>, String> {
+    if text.contains("_tile_bits") {
+        return Err("packed maps can't be imported".to_string());
+    }
+
+    let mut floors: Vec<Dungeon> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("uint8_t ") {
+        rest = &rest[start..];
+        let (header, body) = rest
+            .split_once('{')
+            .ok_or("floor array is missing its opening brace")?;
+        let (body, after) = body
+            .split_once("};")
+            .ok_or("floor array is missing its closing brace")?;
+        rest = after;
+        if !header.trim_end().ends_with('=') {
+            continue;
+        }
+
+        let numbers = parse_numbers(body)?;
+        let (_, grid) = split_floor(&numbers, floors.len())?;
+        let start_y = start_value(numbers[2], floors.len())?;
+        let start_x = start_value(numbers[3], floors.len())?;
+        floors.push(Dungeon::from_grid(grid, start_x, start_y)?);
+    }
+    Ok(floors)
+}
+
+// Names of the top level `<name> = {` tables and the offset just past the brace
+fn lua_tables(text: &str) -> Vec<(&str, usize)> {
+    let mut tables: Vec<(&str, usize)> = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if let Some(name) = line.trim_end().strip_suffix(" = {") {
+            tables.push((name, offset + line.trim_end().len()));
+        }
+        offset += line.len();
+    }
+    tables
+}
+
+// Floors of a `<name> = { {...}, {...} }` table starting at `start`
+fn lua_floors(text: &str, name: &str, start: usize) -> Result<Vec<Vec<i32>>, String> {
+    let mut floors: Vec<Vec<i32>> = Vec::new();
+    let mut depth = 1;
+    let mut floor_start = 0;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '{' => {
+                depth += 1;
+                floor_start = start + i + 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(floors);
+                }
+                floors.push(parse_numbers(&text[floor_start..start + i])?);
+            }
+            _ => {}
+        }
+    }
+    Err(format!("{} table is never closed", name))
+}
+
+// The lua grid is mirrored left to right and the start is stored as the
+// mirrored x then y. Scripts written with only the raycast grids can't be
+// imported, the room tiles are gone from those.
+fn read_maps_lua(text: &str) -> Result<Vec<(String, Vec<Dungeon>)>, String> {
+    let tables = lua_tables(text);
+    let raycast_of = |name: &str| {
+        let raycast_name = format!("{}_raycast", name);
+        tables
+            .iter()
+            .find(|(table, _)| *table == raycast_name)
+            .cloned()
+    };
+
+    let mut dungeons: Vec<(String, Vec<Dungeon>)> = Vec::new();
+    for &(name, start) in &tables {
+        if name.ends_with("_raycast") {
+            continue;
+        }
+
+        let raycast = match raycast_of(name) {
+            Some((raycast_name, start)) => lua_floors(text, raycast_name, start)?,
+            None => Vec::new(),
+        };
+
+        let mut floors: Vec<Dungeon> = Vec::new();
+        for (i, numbers) in lua_floors(text, name, start)?.iter().enumerate() {
+            let (width, mut grid) = split_floor(numbers, i)?;
+            for row in grid.iter_mut() {
+                row.reverse();
+            }
+            let start_x = (width as u32)
+                .checked_sub(start_value(numbers[2], i)?.saturating_add(1))
+                .ok_or(format!(
+                    "floor {} start {} is outside the map",
+                    i, numbers[2]
+                ))?;
+            let start_y = start_value(numbers[3], i)?;
+
+            let mut d = Dungeon::from_grid(grid, start_x, start_y)?;
+            // Keep the exported raycast layer, it may hold wall textures
+            if let Some(numbers) = raycast.get(i) {
+                let raycast_grid = split_floor(numbers, i)?.1;
+                check_layer(&d, &raycast_grid, "raycast_grid", i)?;
+                d.raycast_grid = raycast_grid;
+            }
+            floors.push(d);
+        }
+        dungeons.push((name.to_string(), floors));
+    }
+    if dungeons.is_empty() && !tables.is_empty() {
+        return Err(
+            "only raycast grids in this script, write it with lua_grids normal or both".to_string(),
+        );
+    }
+    Ok(dungeons)
+}
+
+#[derive(Deserialize)]
+struct DungeonJson {
+    schema_version: u32,
+    name: String,
+    floors: Vec<FloorJson>,
+}
+
+#[derive(Deserialize)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[derive(Deserialize)]
+struct FloorJson {
+    #[serde(default)]
+    seed: Option<u64>,
+    start: Point,
+    grid: Vec<Vec<i32>>,
+    raycast_grid: Vec<Vec<i32>>,
+    #[serde(default)]
+    autotile_grid: Vec<Vec<i32>>,
+}
+
+fn read_json(text: &str) -> Result<(String, Vec<Dungeon>), String> {
+    let dungeon: DungeonJson = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if dungeon.schema_version > JSON_SCHEMA_VERSION {
+        return Err(format!(
+            "schema version {} is newer than the supported {}",
+            dungeon.schema_version, JSON_SCHEMA_VERSION
+        ));
+    }
+
+    let floors = dungeon
+        .floors
+        .into_iter()
+        .enumerate()
+        .map(|(i, floor)| {
+            let mut d = Dungeon::from_grid(floor.grid, floor.start.x, floor.start.y)?;
+            check_layer(&d, &floor.raycast_grid, "raycast_grid", i)?;
+            check_layer(&d, &floor.autotile_grid, "autotile_grid", i)?;
+            d.seed = floor.seed;
+            d.raycast_grid = generator::mirror_rows(&floor.raycast_grid);
+            d.autotile_grid = floor.autotile_grid;
+            Ok(d)
+        })
+        .collect::<Result<Vec<Dungeon>, String>>()?;
+    Ok((dungeon.name, floors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exporter;
+    use crate::generator::paramaters::{Compression, DungeonParameters, LuaGrids, TileEncoding};
+    use crate::generator::Layer;
+
+    fn floors() -> Vec<Dungeon> {
+        let params: DungeonParameters = serde_json::from_str(
+            r#"{
+                "dungeons": [{
+                    "name": "test_dungeon",
+                    "height": 16,
+                    "width": 30,
+                    "room_types": ["treasure", "boss", "shop", "secret"],
+                    "extra_room_types": 0,
+                    "amount_of_rooms": 8,
+                    "room_size_low": 3,
+                    "room_size_high": 6,
+                    "start_finish_min_distance": 10,
+                    "room_padding_density": 2,
+                    "sparse": false,
+                    "count": 2,
+                    "seed": 7
+                }],
+                "file_path": "",
+                "include_images": false
+            }"#,
+        )
+        .unwrap();
+        generator::new_dungeon(&params).unwrap().remove(0)
+    }
+
+    // A directory of its own per test, the tests run in parallel
+    fn temp_dir(test: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("dungeonsprawler_{}_{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        format!("{}/", dir.display())
+    }
+
+    fn assert_same_floors(exported: &[Dungeon], imported: &[Dungeon]) {
+        assert_eq!(exported.len(), imported.len());
+        for (a, b) in exported.iter().zip(imported) {
+            assert_eq!(a.grid, b.grid);
+            assert_eq!((a.start_x, a.start_y), (b.start_x, b.start_y));
+            assert_eq!((a.end_x, a.end_y), (b.end_x, b.end_y));
+            assert_eq!(a.raycast_grid, b.raycast_grid);
+        }
+    }
+
+    #[test]
+    fn fx_round_trip() {
+        let floors = floors();
+        let dir = temp_dir("fx");
+        exporter::write_dungeons_to_file(
+            &floors,
+            "fx_dungeon",
+            &dir,
+            Layer::Grid,
+            TileEncoding::Byte,
+            Compression::None,
+        )
+        .unwrap();
+
+        let dungeons = read_dungeons(&format!("{}fx_dungeon_maps.txt", dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(dungeons.len(), 1);
+        assert_eq!(dungeons[0].0, "fx_dungeon");
+        assert_same_floors(&floors, &dungeons[0].1);
+    }
+
+    #[test]
+    fn lua_round_trip() {
+        let floors = floors();
+        for grids in [LuaGrids::Both, LuaGrids::Normal] {
+            let dir = temp_dir(&format!("lua_{:?}", grids));
+            exporter::write_dungeons_to_lua(&[("lua_dungeon", &floors)], &dir, grids).unwrap();
+
+            let dungeons = read_dungeons(&format!("{}lua_dungeon_maps.lua", dir)).unwrap();
+            fs::remove_dir_all(&dir).unwrap();
+            assert_eq!(dungeons.len(), 1);
+            assert_eq!(dungeons[0].0, "lua_dungeon");
+            // The lua grid is mirrored, the import has to turn it back
+            assert_same_floors(&floors, &dungeons[0].1);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let floors = floors();
+        let dir = temp_dir("json");
+        exporter::write_dungeons_to_json(&floors, "json_dungeon", &dir).unwrap();
+
+        let dungeons = read_dungeons(&format!("{}json_dungeon.json", dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(dungeons.len(), 1);
+        assert_eq!(dungeons[0].0, "json_dungeon");
+        assert_same_floors(&floors, &dungeons[0].1);
+        for (a, b) in floors.iter().zip(&dungeons[0].1) {
+            assert_eq!(a.seed, b.seed);
+        }
+    }
+
+    #[test]
+    fn autotile_layers_are_rejected() {
+        let mut floors = floors();
+        for floor in floors.iter_mut() {
+            floor.autotile_grid = floor.grid.clone();
+        }
+        let dir = temp_dir("autotile");
+        exporter::write_dungeons_to_file(
+            &floors,
+            "a_autotile",
+            &dir,
+            Layer::Autotile,
+            TileEncoding::Byte,
+            Compression::None,
+        )
+        .unwrap();
+
+        let result = read_dungeons(&format!("{}a_autotile_maps.txt", dir));
+        fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(e) => assert!(e.to_string().contains("autotile")),
+            Ok(_) => panic!("an autotile layer was imported"),
+        }
+    }
+
+    #[test]
+    fn bad_floor_headers() {
+        assert!(split_floor(&[-1, 40, 1, 1, 1], 0).is_err());
+        assert!(split_floor(&[i32::MAX, i32::MAX, 1, 1, 1], 0).is_err());
+        assert!(split_floor(&[2, 2, 0, 0, 1, 1, 1], 0).is_err());
+        assert!(read_maps_txt("uint8_t x0 = {\n1, 1,\n-1, 0,\n4,\n};").is_err());
+    }
+}
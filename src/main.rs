@@ -1,5 +1,4 @@
-mod exporter;
-mod generator;
+use dungeonsprawler::{exporter, generator, importer};
 use std::env;
 use std::fs::File;
 use std::io::Read;
@@ -13,6 +12,32 @@ fn main() {
         panic!("Please provide a JSON file as a command-line argument");
     }
 
+    let mut analyze_samples: Option<u32> = None;
+    let mut imports: Vec<String> = Vec::new();
+    let mut preview = false;
+    let mut colour = false;
+    let mut flags = args[2..].iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--analyze" => {
+                let samples = flags
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--analyze expects the number of floors to generate");
+                analyze_samples = Some(samples);
+            }
+            "--import" => {
+                let path = flags
+                    .next()
+                    .expect("--import expects a _maps.txt, _maps.lua or .json file");
+                imports.push(path.clone());
+            }
+            "--preview" => preview = true,
+            "--colour" | "--color" => colour = true,
+            _ => panic!("Unknown argument {}", flag),
+        }
+    }
+
     let json_file_path = &args[1];
     if !Path::new(json_file_path).exists() {
         panic!("The file {} does not exist", json_file_path);
@@ -25,19 +50,92 @@ fn main() {
 
     let params = generator::paramaters::marshal_from_json(&json_content).unwrap();
     let outpath = params.file_path.clone();
+    let registry = exporter::Registry::new();
+    let exporters = match registry.resolve(&params.output_formats()) {
+        Ok(exporters) => exporters,
+        Err(e) => panic!("{}", e),
+    };
+
+    if let Some(samples) = analyze_samples {
+        for report in generator::analyze(&params, samples) {
+            println!("{}", report);
+        }
+        return;
+    }
 
-    let d = generator::new_dungeon(&params);
+    // Imported maps skip generation and go straight to the exporters
+    let d: Vec<(String, Vec<generator::Dungeon>)> = if imports.is_empty() {
+        match generator::new_dungeon(&params) {
+            Ok(d) => params
+                .dungeons
+                .iter()
+                .map(|options| options.name.clone())
+                .zip(d)
+                .collect(),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        imports
+            .iter()
+            .flat_map(|path| match importer::read_dungeons(path) {
+                Ok(dungeons) => dungeons,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            })
+            .collect()
+    };
 
-    for (i, dungeon) in d.iter().enumerate() {
-        let name = params.dungeons[i].name.clone();
+    // Only draw the floors, none of the output formats are written
+    if preview {
+        for (name, dungeon) in &d {
+            for (j, floor) in dungeon.iter().enumerate() {
+                println!(
+                    "{}{}:\n{}",
+                    name,
+                    j,
+                    exporter::render_ascii(floor, &params.ascii, colour)
+                );
+            }
+        }
+        return;
+    }
 
-        if params.output_format == "raycast" {
-            // let name = params.dungeons[i].name.clone() + "_raycast";
-            // exporter::write_dungeons_to_file(&dungeon, &name, &outpath, true).unwrap();
-            let name = params.dungeons[i].name.clone();
-            exporter::write_dungeons_to_lua(&dungeon, &name, &outpath).unwrap();
-        } else {
-            exporter::write_dungeons_to_file(&dungeon, &name, &outpath, false).unwrap();
+    if !imports.is_empty() && params.include_images {
+        for (name, dungeon) in &d {
+            generator::paint_images(dungeon, name, &outpath);
         }
     }
+
+    for (name, dungeon) in &d {
+        let name = name.clone();
+
+        for (j, floor) in dungeon.iter().enumerate() {
+            println!("{}{}:\n{}", name, j, floor.stats);
+        }
+        exporter::write_stats_to_file(dungeon, &name, &outpath).unwrap();
+    }
+
+    // fx, binary and header all report the same compressed sizes
+    let mut notes: Vec<String> = Vec::new();
+    for exporter in &exporters {
+        for (name, dungeon) in &d {
+            for note in exporter.notes(dungeon, name, &params).unwrap() {
+                if !notes.contains(&note) {
+                    notes.push(note);
+                }
+            }
+        }
+    }
+    for note in &notes {
+        println!("{}", note);
+    }
+
+    for exporter in &exporters {
+        exporter.write(&d, &params).unwrap();
+    }
 }